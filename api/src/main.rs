@@ -0,0 +1,383 @@
+use anyhow::Context as AnyhowContext;
+use hyper::{
+    header::HeaderValue,
+    server::conn::AddrStream,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use mcping::{
+    tokio::AsyncPingable, AddressPolicy, Auto, Bedrock, CacheKey, CacheOptions, Cached, ErrorKind,
+    Java, Quota, RateLimitOptions, RateLimiter, ResponseCache,
+};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    convert::Infallible, fs, future::Future, io, net::IpAddr, net::SocketAddr, sync::Arc,
+    time::Duration,
+};
+
+mod status;
+
+use status::{Offline, Online};
+
+/// The OpenAPI description of the API, served at `/openapi.json`.
+const OPENAPI: &str = include_str!("../openapi.json");
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let cfg = match std::env::args().nth(1) {
+        Some(config_file) => load_config(&config_file).with_context(|| "failed to load config")?,
+        None => toml::from_str("").expect("the default config is valid"),
+    };
+    let listen = cfg.listen;
+
+    let state = Arc::new(State {
+        cache: ResponseCache::new(CacheOptions {
+            ttl: Duration::from_secs(cfg.cache_ttl),
+            negative_ttl: Some(Duration::from_secs(cfg.error_ttl)),
+            stale_while_revalidate: None,
+        }),
+        limiter: RateLimiter::new(RateLimitOptions {
+            per_ip: Some(Quota {
+                burst: cfg.rate_limit.burst.max(1),
+                interval: Duration::from_secs(60) / cfg.rate_limit.per_minute.max(1),
+            }),
+            per_host: None,
+            max_delay: Some(Duration::ZERO),
+        }),
+        policy: if cfg.allow_private {
+            None
+        } else {
+            Some(AddressPolicy::default())
+        },
+        cfg,
+    });
+
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let state = Arc::clone(&state);
+        let remote = conn.remote_addr().ip();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let state = Arc::clone(&state);
+                async move { Ok::<_, Infallible>(state.handle(remote, req).await) }
+            }))
+        }
+    });
+
+    eprintln!("listening on http://{}", listen);
+    Server::try_bind(&listen)
+        .with_context(|| format!("failed to listen on {}", listen))?
+        .serve(make_service)
+        .await
+        .with_context(|| "server error")
+}
+
+/// Configuration file with the address to listen on and how to treat clients.
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(default = "default_listen")]
+    listen: SocketAddr,
+    /// The timeout of a whole ping, in seconds.
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+    /// How long a response is served from the cache, in seconds.
+    #[serde(default = "default_cache_ttl")]
+    cache_ttl: u64,
+    /// How long a failed ping is served from the cache, in seconds.
+    #[serde(default = "default_error_ttl")]
+    error_ttl: u64,
+    /// Take the client address from the last entry of `X-Forwarded-For`, when
+    /// behind a proxy.
+    #[serde(default)]
+    trust_forwarded_for: bool,
+    /// Allow pinging private, loopback, link-local and multicast addresses.
+    #[serde(default)]
+    allow_private: bool,
+    #[serde(default)]
+    rate_limit: RateLimit,
+}
+
+/// How many requests each client may make.
+#[derive(Debug, Deserialize)]
+struct RateLimit {
+    /// The number of requests that can be made at once.
+    #[serde(default = "default_burst")]
+    burst: u32,
+    /// The number of requests a minute that can be made after that.
+    #[serde(default = "default_per_minute")]
+    per_minute: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            burst: default_burst(),
+            per_minute: default_per_minute(),
+        }
+    }
+}
+
+fn default_listen() -> SocketAddr {
+    ([0, 0, 0, 0], 8080).into()
+}
+
+fn default_timeout() -> u64 {
+    5
+}
+
+fn default_cache_ttl() -> u64 {
+    60
+}
+
+fn default_error_ttl() -> u64 {
+    10
+}
+
+fn default_burst() -> u32 {
+    10
+}
+
+fn default_per_minute() -> u32 {
+    60
+}
+
+/// Loads the config file at `path`.
+fn load_config(path: &str) -> Result<Config, anyhow::Error> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read file '{}'", path))?;
+    toml::from_str(&contents)
+        .with_context(|| format!("failed to parse TOML loaded from file '{}'", path))
+}
+
+struct State {
+    cfg: Config,
+    cache: ResponseCache,
+    limiter: RateLimiter,
+    policy: Option<AddressPolicy>,
+}
+
+impl State {
+    async fn handle(&self, remote: IpAddr, req: Request<Body>) -> Response<Body> {
+        if req.method() != Method::GET {
+            return error(StatusCode::METHOD_NOT_ALLOWED, "only GET is supported");
+        }
+
+        let path = req.uri().path();
+        if path == "/openapi.json" {
+            return respond(StatusCode::OK, "application/json", OPENAPI);
+        }
+
+        let (route, address) = match path
+            .strip_prefix("/v1/")
+            .and_then(|rest| rest.split_once('/'))
+        {
+            Some(parts) => parts,
+            None => return error(StatusCode::NOT_FOUND, "see /openapi.json for the routes"),
+        };
+        let (route, address) = match (route, address.strip_suffix(".png")) {
+            ("icon", Some(address)) => ("icon", address),
+            ("java", _) | ("bedrock", _) | ("auto", _) => (route, address),
+            _ => return error(StatusCode::NOT_FOUND, "see /openapi.json for the routes"),
+        };
+        let address = match percent_decode_str(address).decode_utf8() {
+            Ok(address) if !address.is_empty() => address.into_owned(),
+            _ => return error(StatusCode::BAD_REQUEST, "invalid server address"),
+        };
+
+        if let Err(mcping::Error::RateLimited { retry_after, .. }) =
+            self.limiter.try_acquire(self.client(remote, &req))
+        {
+            let mut response = error(StatusCode::TOO_MANY_REQUESTS, "too many requests");
+            response.headers_mut().insert(
+                "Retry-After",
+                HeaderValue::from(retry_after.as_secs_f64().ceil() as u64),
+            );
+            return response;
+        }
+
+        match route {
+            "java" => self.java(address).await,
+            "bedrock" => self.bedrock(address).await,
+            "auto" => self.auto(address).await,
+            _ => self.icon(address).await,
+        }
+    }
+
+    /// The address of the client making `req`.
+    ///
+    /// Behind a proxy this is the last address in `X-Forwarded-For`, the one
+    /// the proxy added. The addresses before it are up to the client.
+    fn client(&self, remote: IpAddr, req: &Request<Body>) -> IpAddr {
+        if !self.cfg.trust_forwarded_for {
+            return remote;
+        }
+
+        req.headers()
+            .get("X-Forwarded-For")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|client| client.trim().parse().ok())
+            .unwrap_or(remote)
+    }
+
+    async fn java(&self, server_address: String) -> Response<Body> {
+        let result = self
+            .ping(Java {
+                server_address: server_address.clone(),
+                timeout: Some(self.timeout()),
+                address_policy: self.policy.clone(),
+                ..Default::default()
+            })
+            .await;
+
+        self.status(
+            &server_address,
+            result.map(|(latency, r)| status::Java::new(latency, r)),
+        )
+    }
+
+    async fn bedrock(&self, server_address: String) -> Response<Body> {
+        let result = self
+            .ping(Bedrock {
+                server_address: server_address.clone(),
+                timeout: Some(self.timeout()),
+                address_policy: self.policy.clone(),
+                ..Default::default()
+            })
+            .await;
+
+        self.status(
+            &server_address,
+            result.map(|(latency, r)| status::Bedrock::new(latency, r)),
+        )
+    }
+
+    async fn auto(&self, server_address: String) -> Response<Body> {
+        let result = self
+            .ping(Auto {
+                server_address: server_address.clone(),
+                timeout: Some(self.timeout()),
+                address_policy: self.policy.clone(),
+                ..Default::default()
+            })
+            .await;
+
+        self.status(&server_address, result.map(|(_, r)| status::Auto::new(r)))
+    }
+
+    /// The server icon, as the PNG image the server sent.
+    async fn icon(&self, server_address: String) -> Response<Body> {
+        let result = self
+            .ping(Java {
+                server_address: server_address.clone(),
+                timeout: Some(self.timeout()),
+                address_policy: self.policy.clone(),
+                ..Default::default()
+            })
+            .await;
+
+        let favicon = match result {
+            Ok((_, response)) => response.decode_favicon(),
+            Err(e) => match client_error(&e) {
+                Some(status) => return error(status, &e.to_string()),
+                None => Ok(None),
+            },
+        };
+
+        match favicon {
+            Ok(Some(favicon)) => {
+                self.cacheable(respond(StatusCode::OK, "image/png", favicon.into_png()))
+            }
+            Ok(None) => error(StatusCode::NOT_FOUND, "the server has no icon"),
+            // The server answered, but with an icon that can't be served.
+            Err(e) => {
+                let e = mcping::Error::from(e);
+                eprintln!("{}: {}", server_address, e);
+                error(StatusCode::BAD_GATEWAY, &e.to_string())
+            }
+        }
+    }
+
+    /// Ping through the cache, giving up after the configured timeout.
+    fn ping<P>(
+        &self,
+        pingable: P,
+    ) -> impl Future<Output = Result<(Duration, P::Response), mcping::Error>>
+    where
+        P: AsyncPingable + CacheKey + Send + 'static,
+        P::Response: Clone + Send + Sync + 'static,
+    {
+        let timeout = self.timeout();
+        let ping = mcping::tokio::get_status(Cached {
+            pingable,
+            cache: self.cache.clone(),
+        });
+
+        async move {
+            match tokio::time::timeout(timeout, ping).await {
+                Ok(result) => result,
+                Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "the ping timed out").into()),
+            }
+        }
+    }
+
+    /// Respond with the status of a server, or why it couldn't be pinged.
+    fn status<T: Serialize>(
+        &self,
+        address: &str,
+        result: Result<T, mcping::Error>,
+    ) -> Response<Body> {
+        let body = match result {
+            Ok(status) => serde_json::to_string(&Online::new(address, status)),
+            Err(e) => match client_error(&e) {
+                Some(status) => return error(status, &e.to_string()),
+                None => serde_json::to_string(&Offline::new(address, &e)),
+            },
+        };
+
+        match body {
+            Ok(body) => self.cacheable(respond(StatusCode::OK, "application/json", body)),
+            Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+        }
+    }
+
+    fn cacheable(&self, mut response: Response<Body>) -> Response<Body> {
+        if let Ok(value) = HeaderValue::from_str(&format!("public, max-age={}", self.cfg.cache_ttl))
+        {
+            response.headers_mut().insert("Cache-Control", value);
+        }
+        response
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.cfg.timeout)
+    }
+}
+
+/// The status to respond with when a ping failed because of the request,
+/// rather than the server.
+fn client_error(error: &mcping::Error) -> Option<StatusCode> {
+    match error.kind() {
+        ErrorKind::InvalidAddress => Some(StatusCode::BAD_REQUEST),
+        ErrorKind::AddressBlocked => Some(StatusCode::FORBIDDEN),
+        _ => None,
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    respond(
+        status,
+        "application/json",
+        json!({ "error": message }).to_string(),
+    )
+}
+
+fn respond(status: StatusCode, content_type: &str, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        .header("Access-Control-Allow-Origin", "*")
+        .body(body.into())
+        .expect("the response is valid")
+}
@@ -0,0 +1,113 @@
+//! The JSON bodies of status responses.
+
+use mcping::{AutoResponse, BedrockResponse, Chat, JavaResponse};
+use serde::Serialize;
+use std::time::Duration;
+
+/// A server that answered.
+#[derive(Debug, Serialize)]
+pub struct Online<'a, T> {
+    online: bool,
+    address: &'a str,
+    #[serde(flatten)]
+    status: T,
+}
+
+impl<'a, T> Online<'a, T> {
+    pub fn new(address: &'a str, status: T) -> Self {
+        Self {
+            online: true,
+            address,
+            status,
+        }
+    }
+}
+
+/// A server that couldn't be pinged.
+#[derive(Debug, Serialize)]
+pub struct Offline<'a> {
+    online: bool,
+    address: &'a str,
+    error: String,
+}
+
+impl<'a> Offline<'a> {
+    pub fn new(address: &'a str, error: &mcping::Error) -> Self {
+        Self {
+            online: false,
+            address,
+            error: error.to_string(),
+        }
+    }
+}
+
+/// The MOTD as the server sent it, and as plain text.
+#[derive(Debug, Serialize)]
+pub struct Motd<M> {
+    raw: M,
+    plain: String,
+}
+
+/// The status of a Java server, with the response as the server sent it.
+#[derive(Debug, Serialize)]
+pub struct Java {
+    latency_ms: f64,
+    motd: Motd<Chat>,
+    response: JavaResponse,
+}
+
+impl Java {
+    pub fn new(latency: Duration, response: JavaResponse) -> Self {
+        Self {
+            latency_ms: latency.as_secs_f64() * 1000.0,
+            motd: Motd {
+                plain: response.description.plain_text(),
+                raw: response.description.clone(),
+            },
+            response,
+        }
+    }
+}
+
+/// The status of a Bedrock server, with every field of its response.
+#[derive(Debug, Serialize)]
+pub struct Bedrock {
+    latency_ms: f64,
+    motd: Motd<String>,
+    response: BedrockResponse,
+}
+
+impl Bedrock {
+    pub fn new(latency: Duration, response: BedrockResponse) -> Self {
+        let raw = match &response.motd_2 {
+            Some(motd_2) => format!("{}\n{}", response.motd_1, motd_2),
+            None => response.motd_1.clone(),
+        };
+
+        Self {
+            latency_ms: latency.as_secs_f64() * 1000.0,
+            motd: Motd {
+                plain: Chat::String(raw.clone()).plain_text(),
+                raw,
+            },
+            response,
+        }
+    }
+}
+
+/// The status of a server for each edition that answered.
+#[derive(Debug, Serialize)]
+pub struct Auto {
+    java: Option<Java>,
+    bedrock: Option<Bedrock>,
+}
+
+impl Auto {
+    pub fn new(response: AutoResponse) -> Self {
+        let (java, bedrock) = response.into_parts();
+        Self {
+            java: java.map(|(latency, response)| Java::new(latency, response)),
+            bedrock: bedrock.map(|(latency, response)| Bedrock::new(latency, response)),
+        }
+    }
+}
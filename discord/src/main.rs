@@ -77,20 +77,32 @@ impl EventHandler for Handler {
         let status = mcping::get_status(mcping::Java {
             server_address: self.addr.clone(),
             timeout: Some(Duration::from_secs(10)),
+            ..Default::default()
         });
 
         let res = status.map(|(ping, r)| {
-            // The icon is a base64 encoded PNG so we must decode that first.
-            let icon = r
-                .favicon
-                .map(|i| {
-                    base64::decode_config(
-                        i.trim_start_matches("data:image/png;base64,"),
-                        base64::STANDARD,
-                    )
-                })
-                .transpose()
-                .unwrap_or(None);
+            let icon = r.decode_favicon();
+
+            // Draw the server as the multiplayer screen lists it, with the
+            // placeholder icon if the server's is invalid.
+            let mut entry = mcping::ListEntry::java(ping, &r);
+            entry.name = self.addr.clone();
+            if icon.is_err() {
+                entry.icon = None;
+            }
+            let entry = match entry.to_png(2) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    println!("Error drawing server list entry: {}", e);
+                    None
+                }
+            };
+
+            // An invalid icon is reported rather than failing the command.
+            let (icon, icon_error) = match icon {
+                Ok(icon) => (icon.map(mcping::Favicon::into_png), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
 
             let sanitize = |s: &str| {
                 s.chars().fold(String::with_capacity(s.len()), |mut s, c| {
@@ -114,6 +126,8 @@ impl EventHandler for Handler {
 
             (
                 icon,
+                icon_error,
+                entry,
                 r.description,
                 r.players.online,
                 r.players.max,
@@ -124,31 +138,45 @@ impl EventHandler for Handler {
 
         // Attempt to send a message to this channel.
         let msg = match res {
-            Ok((icon, desc, online, max, sample, ping)) => chan.send_message(&context.http, |m| {
-                m.embed(|e| {
-                    e.title(desc.text())
-                        .fields(vec![
-                            ("Players", format!("{}/{}", online, max), true),
-                            ("Online", sample, true),
-                        ])
-                        .footer(|f| f.text(format!("{} | {} ms", &self.addr, ping)));
-
-                    if icon.is_some() {
-                        e.thumbnail("attachment://icon.png");
-                    }
-
-                    e
-                });
+            Ok((icon, icon_error, entry, desc, online, max, sample, ping)) => {
+                chan.send_message(&context.http, |m| {
+                    m.embed(|e| {
+                        e.description(desc.to_discord())
+                            .fields(vec![
+                                ("Players", format!("{}/{}", online, max), true),
+                                ("Online", sample, true),
+                            ])
+                            .footer(|f| f.text(format!("{} | {} ms", &self.addr, ping)));
+
+                        if icon.is_some() {
+                            e.thumbnail("attachment://icon.png");
+                        }
+                        if let Some(error) = &icon_error {
+                            e.field("Icon", format!("Invalid: {}", error), false);
+                        }
+                        if entry.is_some() {
+                            e.image("attachment://entry.png");
+                        }
 
-                if let Some(icon) = icon {
-                    m.add_file(AttachmentType::Bytes {
-                        data: icon.into(),
-                        filename: String::from("icon.png"),
+                        e
                     });
-                }
 
-                m
-            }),
+                    if let Some(icon) = icon {
+                        m.add_file(AttachmentType::Bytes {
+                            data: icon.into(),
+                            filename: String::from("icon.png"),
+                        });
+                    }
+                    if let Some(entry) = entry {
+                        m.add_file(AttachmentType::Bytes {
+                            data: entry.into(),
+                            filename: String::from("entry.png"),
+                        });
+                    }
+
+                    m
+                })
+            }
             Err(err) => {
                 // If there is an error we will send a message with the error content.
                 chan.send_message(&context.http, |m| {
@@ -0,0 +1,256 @@
+use anyhow::Context as AnyhowContext;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use mcping::{Auto, Bedrock, Java, ServerStatus};
+use serde::Deserialize;
+use std::{
+    convert::Infallible,
+    fs, io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+mod metrics;
+
+use metrics::Metrics;
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let config_file = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "exporter.toml".to_string());
+    let cfg = load_config(&config_file).with_context(|| "failed to load config")?;
+    let listen = cfg.listen;
+
+    let state = Arc::new(State {
+        cfg,
+        metrics: Mutex::new(Metrics::default()),
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let state = Arc::clone(&state);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let state = Arc::clone(&state);
+                async move { Ok::<_, Infallible>(state.handle(req).await) }
+            }))
+        }
+    });
+
+    eprintln!("listening on http://{}", listen);
+    Server::try_bind(&listen)
+        .with_context(|| format!("failed to listen on {}", listen))?
+        .serve(make_service)
+        .await
+        .with_context(|| "server error")
+}
+
+/// Configuration file with the address to listen on and the servers to ping.
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(default = "default_listen")]
+    listen: SocketAddr,
+    /// The timeout of a whole ping, in seconds.
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+    /// The servers pinged on every scrape of `/metrics`.
+    #[serde(default)]
+    targets: Vec<Target>,
+}
+
+fn default_listen() -> SocketAddr {
+    ([0, 0, 0, 0], 9765).into()
+}
+
+fn default_timeout() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Target {
+    address: String,
+    #[serde(default)]
+    edition: Edition,
+}
+
+/// The edition a server is pinged as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edition {
+    Java,
+    Bedrock,
+    #[default]
+    Auto,
+}
+
+impl std::fmt::Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Edition::Java => f.write_str("java"),
+            Edition::Bedrock => f.write_str("bedrock"),
+            Edition::Auto => f.write_str("auto"),
+        }
+    }
+}
+
+impl std::str::FromStr for Edition {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "java" => Ok(Edition::Java),
+            "bedrock" => Ok(Edition::Bedrock),
+            "auto" => Ok(Edition::Auto),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Loads the config file at `path`.
+fn load_config(path: &str) -> Result<Config, anyhow::Error> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read file '{}'", path))?;
+    toml::from_str(&contents)
+        .with_context(|| format!("failed to parse TOML loaded from file '{}'", path))
+}
+
+struct State {
+    cfg: Config,
+    metrics: Mutex<Metrics>,
+}
+
+impl State {
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        if req.method() != Method::GET {
+            return respond(StatusCode::METHOD_NOT_ALLOWED, "only GET is supported\n");
+        }
+
+        match req.uri().path() {
+            "/metrics" => self.metrics().await,
+            "/probe" => self.probe(req.uri().query().unwrap_or("")).await,
+            _ => respond(StatusCode::NOT_FOUND, "try /metrics or /probe?target=...\n"),
+        }
+    }
+
+    /// Ping every configured target and render all of their metrics, which
+    /// accumulate over scrapes.
+    async fn metrics(&self) -> Response<Body> {
+        let targets: Vec<_> = self
+            .cfg
+            .targets
+            .iter()
+            .map(|target| (target.address.clone(), target.edition))
+            .collect();
+
+        let pings: Vec<_> = targets
+            .iter()
+            .cloned()
+            .map(|(address, edition)| {
+                let timeout = self.timeout();
+                tokio::spawn(async move { ping(&address, edition, timeout).await })
+            })
+            .collect();
+
+        for ((address, edition), ping) in targets.iter().zip(pings) {
+            let result = match ping.await {
+                Ok(result) => result,
+                Err(e) => Err(io::Error::other(e).into()),
+            };
+            self.metrics
+                .lock()
+                .unwrap()
+                .record(address, *edition, &result);
+        }
+
+        let body = self.metrics.lock().unwrap().render(&targets);
+        respond(StatusCode::OK, body)
+    }
+
+    /// Ping the target given in the query and render its metrics.
+    ///
+    /// Nothing is kept between probes, as anyone can choose their targets.
+    async fn probe(&self, query: &str) -> Response<Body> {
+        let mut target = None;
+        let mut edition = Ok(Edition::Auto);
+
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            match &*key {
+                "target" => target = Some(value.into_owned()),
+                "edition" => edition = value.parse(),
+                _ => {}
+            }
+        }
+
+        let (target, edition) = match (target, edition) {
+            (Some(target), Ok(edition)) => (target, edition),
+            (None, _) => return respond(StatusCode::BAD_REQUEST, "missing target\n"),
+            (_, Err(())) => {
+                return respond(
+                    StatusCode::BAD_REQUEST,
+                    "edition must be java, bedrock or auto\n",
+                )
+            }
+        };
+
+        let result = ping(&target, edition, self.timeout()).await;
+
+        let mut metrics = Metrics::default();
+        metrics.record(&target, edition, &result);
+        let body = metrics.render(&[(target, edition)]);
+        respond(StatusCode::OK, body)
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.cfg.timeout)
+    }
+}
+
+/// Ping `address` as `edition`, giving up after `timeout`.
+async fn ping(
+    address: &str,
+    edition: Edition,
+    timeout: Duration,
+) -> Result<ServerStatus, mcping::Error> {
+    let server_address = address.to_string();
+    let ping = async {
+        match edition {
+            Edition::Java => mcping::tokio::get_status(Java {
+                server_address,
+                timeout: Some(timeout),
+                ..Default::default()
+            })
+            .await
+            .map(ServerStatus::from),
+            Edition::Bedrock => mcping::tokio::get_status(Bedrock {
+                server_address,
+                timeout: Some(timeout),
+                ..Default::default()
+            })
+            .await
+            .map(ServerStatus::from),
+            Edition::Auto => mcping::tokio::get_status(Auto {
+                server_address,
+                timeout: Some(timeout),
+                ..Default::default()
+            })
+            .await
+            .map(ServerStatus::from),
+        }
+    };
+
+    match tokio::time::timeout(timeout, ping).await {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "the ping timed out").into()),
+    }
+}
+
+fn respond(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body(body.into())
+        .expect("the response is valid")
+}
@@ -0,0 +1,323 @@
+//! Accumulating ping results and rendering them in the Prometheus text format.
+
+use mcping::{Error, ErrorKind, ServerStatus};
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+
+use crate::Edition;
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Everything recorded about every target pinged so far.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    series: BTreeMap<(String, Edition), Series>,
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    up: bool,
+    players_online: Option<i64>,
+    players_max: Option<i64>,
+    protocol: Option<i64>,
+    latency: Histogram,
+    /// The number of failed pings by the phase they failed in and their error.
+    errors: BTreeMap<(&'static str, &'static str), u64>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// The number of observations in each bucket, not counting smaller ones.
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+
+        if let Some(bucket) = BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+impl Metrics {
+    /// Record the result of pinging `target`.
+    pub fn record(&mut self, target: &str, edition: Edition, result: &Result<ServerStatus, Error>) {
+        let series = self
+            .series
+            .entry((target.to_string(), edition))
+            .or_default();
+
+        series.up = result.is_ok();
+        series.players_online = None;
+        series.players_max = None;
+        series.protocol = None;
+
+        match result {
+            Ok(status) => {
+                series.players_online = status.players_online;
+                series.players_max = status.players_max;
+                series.protocol = status.protocol;
+                series.latency.observe(status.latency);
+            }
+            Err(e) => {
+                let kind = e.kind();
+                *series.errors.entry((phase(kind), error(kind))).or_default() += 1;
+            }
+        }
+    }
+
+    /// Render the metrics of the given targets.
+    pub fn render(&self, targets: &[(String, Edition)]) -> String {
+        let series: Vec<_> = targets
+            .iter()
+            .filter_map(|key| Some((labels(&key.0, key.1), self.series.get(key)?)))
+            .collect();
+
+        let mut out = String::new();
+
+        gauge(
+            &mut out,
+            "mcping_up",
+            "Whether the last ping succeeded.",
+            &series,
+            |s| Some(s.up as i64),
+        );
+        gauge(
+            &mut out,
+            "mcping_players_online",
+            "The number of players online.",
+            &series,
+            |s| s.players_online,
+        );
+        gauge(
+            &mut out,
+            "mcping_players_max",
+            "The maximum number of players online at once.",
+            &series,
+            |s| s.players_max,
+        );
+        gauge(
+            &mut out,
+            "mcping_protocol_version",
+            "The protocol version of the server.",
+            &series,
+            |s| s.protocol,
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP mcping_latency_seconds The latency of successful pings."
+        );
+        let _ = writeln!(out, "# TYPE mcping_latency_seconds histogram");
+        for (labels, s) in &series {
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(&s.latency.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "mcping_latency_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "mcping_latency_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, s.latency.count
+            );
+            let _ = writeln!(
+                out,
+                "mcping_latency_seconds_sum{{{}}} {}",
+                labels, s.latency.sum
+            );
+            let _ = writeln!(
+                out,
+                "mcping_latency_seconds_count{{{}}} {}",
+                labels, s.latency.count
+            );
+        }
+
+        let _ = writeln!(out, "# HELP mcping_ping_errors_total The number of failed pings by the phase they failed in and their error.");
+        let _ = writeln!(out, "# TYPE mcping_ping_errors_total counter");
+        for (labels, s) in &series {
+            for ((phase, error), count) in &s.errors {
+                let _ = writeln!(
+                    out,
+                    "mcping_ping_errors_total{{{},phase=\"{}\",error=\"{}\"}} {}",
+                    labels, phase, error, count
+                );
+            }
+        }
+
+        out
+    }
+}
+
+fn gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    series: &[(String, &Series)],
+    value: impl Fn(&Series) -> Option<i64>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+
+    for (labels, s) in series {
+        if let Some(value) = value(s) {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+}
+
+fn labels(target: &str, edition: Edition) -> String {
+    let mut escaped = String::with_capacity(target.len());
+    for c in target.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+
+    format!("target=\"{}\",edition=\"{}\"", escaped, edition)
+}
+
+/// The phase of the ping an error happened in.
+///
+/// Timeouts can happen while connecting or while waiting for the response, so
+/// their phase is unknown.
+fn phase(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::InvalidAddress | ErrorKind::DnsLookupFailed => "resolve",
+        ErrorKind::AddressBlocked | ErrorKind::RateLimited | ErrorKind::ConnectionRefused => {
+            "connect"
+        }
+        ErrorKind::ConnectionReset | ErrorKind::InvalidResponse | ErrorKind::Io => "status",
+        ErrorKind::TimedOut => "unknown",
+    }
+}
+
+/// The value of the `error` label of an error.
+fn error(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::InvalidAddress => "invalid_address",
+        ErrorKind::DnsLookupFailed => "dns_lookup_failed",
+        ErrorKind::TimedOut => "timed_out",
+        ErrorKind::ConnectionRefused => "connection_refused",
+        ErrorKind::ConnectionReset => "connection_reset",
+        ErrorKind::InvalidResponse => "invalid_response",
+        ErrorKind::AddressBlocked => "address_blocked",
+        ErrorKind::RateLimited => "rate_limited",
+        ErrorKind::Io => "io",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcping::Chat;
+    use std::io;
+
+    fn status(latency: Duration) -> ServerStatus {
+        ServerStatus {
+            edition: mcping::Edition::Java,
+            version_name: "1.16.5".to_string(),
+            protocol: Some(754),
+            players_online: Some(3),
+            players_max: Some(20),
+            motd: Chat::String("A Minecraft Server".to_string()),
+            icon: None,
+            sample: None,
+            latency,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn renders_a_probe() {
+        let target = "mc.example.com".to_string();
+        let mut metrics = Metrics::default();
+        metrics.record(
+            &target,
+            Edition::Java,
+            &Ok(status(Duration::from_millis(20))),
+        );
+        metrics.record(
+            &target,
+            Edition::Java,
+            &Err(io::Error::new(io::ErrorKind::TimedOut, "the ping timed out").into()),
+        );
+        metrics.record(
+            &target,
+            Edition::Java,
+            &Err(io::Error::from(io::ErrorKind::ConnectionRefused).into()),
+        );
+        metrics.record(
+            &target,
+            Edition::Java,
+            &Ok(status(Duration::from_millis(300))),
+        );
+
+        let out = metrics.render(&[(target, Edition::Java)]);
+        let labels = r#"target="mc.example.com",edition="java""#;
+        for line in &[
+            format!("mcping_up{{{}}} 1", labels),
+            format!("mcping_players_online{{{}}} 3", labels),
+            format!("mcping_players_max{{{}}} 20", labels),
+            format!("mcping_protocol_version{{{}}} 754", labels),
+            format!("mcping_latency_seconds_bucket{{{},le=\"0.01\"}} 0", labels),
+            format!("mcping_latency_seconds_bucket{{{},le=\"0.025\"}} 1", labels),
+            format!("mcping_latency_seconds_bucket{{{},le=\"0.25\"}} 1", labels),
+            format!("mcping_latency_seconds_bucket{{{},le=\"0.5\"}} 2", labels),
+            format!("mcping_latency_seconds_bucket{{{},le=\"+Inf\"}} 2", labels),
+            format!("mcping_latency_seconds_sum{{{}}} 0.32", labels),
+            format!("mcping_latency_seconds_count{{{}}} 2", labels),
+            format!(
+                "mcping_ping_errors_total{{{},phase=\"unknown\",error=\"timed_out\"}} 1",
+                labels
+            ),
+            format!(
+                "mcping_ping_errors_total{{{},phase=\"connect\",error=\"connection_refused\"}} 1",
+                labels
+            ),
+        ] {
+            assert!(
+                out.lines().any(|l| l == line),
+                "missing {:?} in\n{}",
+                line,
+                out
+            );
+        }
+        assert!(!out.contains("phase=\"timeout\""));
+    }
+
+    #[test]
+    fn renders_a_failed_probe() {
+        let target = "down.example.com".to_string();
+        let mut metrics = Metrics::default();
+        metrics.record(
+            &target,
+            Edition::Bedrock,
+            &Err(mcping::Error::DnsLookupFailed),
+        );
+
+        let out = metrics.render(&[(target, Edition::Bedrock)]);
+        let labels = r#"target="down.example.com",edition="bedrock""#;
+        assert!(out
+            .lines()
+            .any(|l| l == format!("mcping_up{{{}}} 0", labels)));
+        assert!(!out.contains("mcping_players_online{"));
+        assert!(out.lines().any(|l| l
+            == format!(
+                "mcping_ping_errors_total{{{},phase=\"resolve\",error=\"dns_lookup_failed\"}} 1",
+                labels
+            )));
+    }
+}
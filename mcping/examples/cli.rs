@@ -1,15 +1,14 @@
 use std::time::Duration;
 
 use argh::FromArgs;
-use mc_legacy_formatting::SpanExt;
-use mcping::{BedrockResponse, JavaResponse};
+use mcping::{AutoResponse, BedrockResponse, Chat, JavaResponse};
 
 #[derive(FromArgs)]
 /// Test out pinging servers, Bedrock or Java edition.
 struct Args {
-    /// the server edition to try and ping
+    /// the server edition to try and ping, detected automatically if omitted
     #[argh(option)]
-    edition: Edition,
+    edition: Option<Edition>,
 
     /// the server address to ping
     #[argh(positional)]
@@ -38,15 +37,25 @@ fn main() -> Result<(), mcping::Error> {
     let args: Args = argh::from_env();
 
     match args.edition {
-        Edition::Java => {
+        None => {
+            let (_, status) = mcping::get_status(mcping::Auto {
+                server_address: args.address,
+                timeout: Some(Duration::from_secs(5)),
+                ..Default::default()
+            })?;
+
+            print_auto(status);
+        }
+        Some(Edition::Java) => {
             let (latency, status) = mcping::get_status(mcping::Java {
                 server_address: args.address,
                 timeout: Some(Duration::from_secs(5)),
+                ..Default::default()
             })?;
 
             print_java(latency, status);
         }
-        Edition::Bedrock => {
+        Some(Edition::Bedrock) => {
             let (latency, status) = mcping::get_status(mcping::Bedrock {
                 server_address: args.address,
                 timeout: Some(Duration::from_secs(5)),
@@ -66,16 +75,27 @@ async fn main() -> Result<(), mcping::Error> {
     let args: Args = argh::from_env();
 
     match args.edition {
-        Edition::Java => {
+        None => {
+            let (_, status) = mcping::tokio::get_status(mcping::Auto {
+                server_address: args.address,
+                timeout: Some(Duration::from_secs(5)),
+                ..Default::default()
+            })
+            .await?;
+
+            print_auto(status);
+        }
+        Some(Edition::Java) => {
             let (latency, status) = mcping::tokio::get_status(mcping::Java {
                 server_address: args.address,
                 timeout: Some(Duration::from_secs(5)),
+                ..Default::default()
             })
             .await?;
 
             print_java(latency, status);
         }
-        Edition::Bedrock => {
+        Some(Edition::Bedrock) => {
             let (latency, status) = mcping::tokio::get_status(mcping::Bedrock {
                 server_address: args.address,
                 timeout: Some(Duration::from_secs(5)),
@@ -90,26 +110,39 @@ async fn main() -> Result<(), mcping::Error> {
     Ok(())
 }
 
-fn print_java(latency: u64, status: JavaResponse) {
+fn print_auto(status: AutoResponse) {
+    let editions = status
+        .editions()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    println!();
+    println!("responded: {}", editions.join(", "));
+
+    let (java, bedrock) = status.into_parts();
+
+    if let Some((latency, status)) = java {
+        print_java(latency, status);
+    }
+
+    if let Some((latency, status)) = bedrock {
+        print_bedrock(latency, status);
+    }
+}
+
+fn print_java(latency: Duration, status: JavaResponse) {
     println!();
-    print!("version: ");
-    status
-        .version
-        .name
-        .span_iter()
-        .map(|s| s.wrap_colored())
-        .for_each(|s| print!("{}", s));
+    print!(
+        "version: {}",
+        Chat::String(status.version.name.clone()).to_ansi()
+    );
 
     println!();
     println!();
 
     println!("description:");
-    status
-        .description
-        .text()
-        .span_iter()
-        .map(|s| s.wrap_colored())
-        .for_each(|s| print!("{}", s));
+    print!("{}", status.description.to_ansi());
 
     println!();
     println!();
@@ -123,56 +156,53 @@ fn print_java(latency: u64, status: JavaResponse) {
     status
         .players
         .sample
+        .as_ref()
         .filter(|sample| !sample.is_empty())
         .map(|sample| {
             println!();
 
             for player in sample {
-                player
-                    .name
-                    .span_iter()
-                    .map(|s| s.wrap_colored())
-                    .for_each(|s| print!("{}", s));
-                println!();
+                println!("{}", Chat::String(player.name.clone()).to_ansi());
             }
         })
         .unwrap_or_else(|| println!("N/A"));
 
-    println!("latency: {}ms", latency);
+    let source = match status
+        .metadata
+        .as_ref()
+        .map(|metadata| metadata.latency_source)
+    {
+        Some(mcping::LatencySource::Status) => " (status round trip, no pong)",
+        _ => "",
+    };
+    println!("latency: {:.2}ms{}", latency.as_secs_f64() * 1000.0, source);
     println!("server icon:\n");
 
-    // The icon is a base64 encoded PNG so we must decode that first.
-    if let Some(icon_bytes) = status
-        .favicon
-        .map(|i| {
-            base64::decode_config(
-                i.trim_start_matches("data:image/png;base64,"),
-                base64::STANDARD,
-            )
-        })
-        .transpose()
-        .unwrap_or(None)
-    {
-        if let Ok(icon_img) =
-            image::load_from_memory_with_format(&icon_bytes, image::ImageFormat::Png)
-        {
-            viuer::print(
-                &icon_img,
-                &viuer::Config {
-                    transparent: true,
-                    absolute_offset: false,
-                    width: Some(32),
-                    ..Default::default()
-                },
-            )
-            .expect("image printing failed");
+    match status.decode_favicon() {
+        Ok(Some(favicon)) => {
+            if let Ok(icon_img) =
+                image::load_from_memory_with_format(favicon.as_png(), image::ImageFormat::Png)
+            {
+                viuer::print(
+                    &icon_img,
+                    &viuer::Config {
+                        transparent: true,
+                        absolute_offset: false,
+                        width: Some(32),
+                        ..Default::default()
+                    },
+                )
+                .expect("image printing failed");
+            }
         }
+        Ok(None) => println!("N/A"),
+        Err(e) => println!("{}", e),
     }
 
     println!();
 }
 
-fn print_bedrock(latency: u64, status: BedrockResponse) {
+fn print_bedrock(latency: Duration, status: BedrockResponse) {
     println!();
     println!("version: {}", &status.version_name);
     println!("edition: {}", &status.edition);
@@ -198,9 +228,7 @@ fn print_bedrock(latency: u64, status: BedrockResponse) {
         status.motd_1.clone()
     };
 
-    motd.span_iter()
-        .map(|s| s.wrap_colored())
-        .for_each(|s| print!("{}", s));
+    print!("{}", Chat::String(motd).to_ansi());
 
     println!();
     println!();
@@ -210,7 +238,7 @@ fn print_bedrock(latency: u64, status: BedrockResponse) {
         &status.players_max.unwrap_or(0)
     );
 
-    println!("latency: {}ms", latency);
+    println!("latency: {:.2}ms", latency.as_secs_f64() * 1000.0);
 
     println!();
 }
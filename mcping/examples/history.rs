@@ -0,0 +1,155 @@
+use std::{
+    convert::TryFrom,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use argh::FromArgs;
+use mcping::{History, Record, Resolution, Retention};
+
+#[derive(FromArgs)]
+/// Record the status of servers, and report on their uptime.
+struct Args {
+    /// the database the history is stored in
+    #[argh(option, default = "String::from(\"history.sqlite3\")")]
+    db: String,
+
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Record(RecordArgs),
+    Report(ReportArgs),
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "record")]
+/// Ping servers once and record the results, e.g. from cron.
+struct RecordArgs {
+    /// the server addresses to ping
+    #[argh(positional)]
+    addresses: Vec<String>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "report")]
+/// Report on the uptime of a server.
+struct ReportArgs {
+    /// the first day of the report (YYYY-MM-DD), 30 days ago if omitted
+    #[argh(option, from_str_fn(parse_date))]
+    from: Option<u64>,
+
+    /// the last day of the report (YYYY-MM-DD), today if omitted
+    #[argh(option, from_str_fn(parse_date))]
+    to: Option<u64>,
+
+    /// the resolution to report at: raw, five_minutes or hourly
+    #[argh(option, default = "Resolution::Raw", from_str_fn(parse_resolution))]
+    resolution: Resolution,
+
+    /// the output format: markdown, json or html
+    #[argh(option, default = "Format::Markdown", from_str_fn(parse_format))]
+    format: Format,
+
+    /// the server address to report on
+    #[argh(positional)]
+    address: String,
+}
+
+/// The format a report is written in.
+enum Format {
+    Markdown,
+    Json,
+    Html,
+}
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn main() -> Result<(), mcping::Error> {
+    let args: Args = argh::from_env();
+
+    // Keep raw records long enough for monthly reports.
+    let history = History::open(
+        &args.db,
+        Retention {
+            raw: Some(Duration::from_secs(400 * DAY)),
+            ..Default::default()
+        },
+    )?;
+
+    match args.command {
+        Command::Record(args) => {
+            for address in args.addresses {
+                let status = mcping::get_status_any(mcping::Auto {
+                    server_address: address.clone(),
+                    timeout: Some(Duration::from_secs(5)),
+                    ..Default::default()
+                });
+                history.record(&Record::new(&address, status.as_ref()))?;
+            }
+        }
+        Command::Report(args) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let to = args.to.map_or(now, |to| to + DAY - 1);
+            let from = args.from.unwrap_or(to - 30 * DAY);
+
+            let report = history.report(&args.address, args.resolution, from, to)?;
+
+            match args.format {
+                Format::Markdown => print!("{}", report.to_markdown()),
+                Format::Json => println!("{}", report.to_json()?),
+                Format::Html => print!("{}", report.to_html()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a `YYYY-MM-DD` date as seconds since the Unix epoch at its start.
+fn parse_date(date: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid date '{}', expected YYYY-MM-DD", date);
+
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>());
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(y)), Some(Ok(m)), Some(Ok(d)))
+            if (1..=12).contains(&m) && (1..=31).contains(&d) =>
+        {
+            (y, m, d)
+        }
+        _ => return Err(invalid()),
+    };
+
+    // Howard Hinnant's `days_from_civil`.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    u64::try_from(days * DAY as i64).map_err(|_| invalid())
+}
+
+fn parse_resolution(resolution: &str) -> Result<Resolution, String> {
+    match resolution {
+        "raw" => Ok(Resolution::Raw),
+        "five_minutes" => Ok(Resolution::FiveMinutes),
+        "hourly" => Ok(Resolution::Hourly),
+        _ => Err("resolution must be raw, five_minutes or hourly".into()),
+    }
+}
+
+fn parse_format(format: &str) -> Result<Format, String> {
+    match format {
+        "markdown" => Ok(Format::Markdown),
+        "json" => Ok(Format::Json),
+        "html" => Ok(Format::Html),
+        _ => Err("format must be markdown, json or html".into()),
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use argh::FromArgs;
+use mcping::{AutoResponse, ListEntry};
+
+#[derive(FromArgs)]
+/// Draw a server as the multiplayer screen lists it.
+struct Args {
+    /// the name shown above the MOTD, the address if omitted
+    #[argh(option)]
+    name: Option<String>,
+
+    /// the GUI scale to draw at
+    #[argh(option, default = "2")]
+    scale: u32,
+
+    /// the server address
+    #[argh(positional)]
+    address: String,
+
+    /// the PNG file to write
+    #[argh(positional)]
+    output: String,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Args = argh::from_env();
+
+    let (_, response) = mcping::get_status(mcping::Auto {
+        server_address: args.address.clone(),
+        timeout: Some(Duration::from_secs(5)),
+        ..Default::default()
+    })?;
+
+    let mut entry = match response {
+        AutoResponse::Java(latency, response)
+        | AutoResponse::Both {
+            java: (latency, response),
+            ..
+        } => ListEntry::java(latency, &response),
+        AutoResponse::Bedrock(latency, response) => ListEntry::bedrock(latency, &response),
+    };
+    entry.name = args.name.unwrap_or(args.address);
+
+    std::fs::write(&args.output, entry.to_png(args.scale)?)?;
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Pinging a server without knowing which edition it is running.
+
+use serde::{Deserialize, Serialize};
+use std::{io, sync::mpsc, thread, time::Duration};
+
+use crate::{
+    AddressPolicy, Bedrock, BedrockResponse, Edition, Error, Java, JavaResponse, Pingable,
+    RateLimiter, ServerStatus,
+};
+
+/// Configuration for pinging a server whose edition is unknown.
+///
+/// A Java status ping and a Bedrock ping are sent at the same time, and the
+/// response of whichever edition answers is returned. Servers that accept both
+/// editions (such as ones running Geyser) return both responses.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::Auto;
+/// use std::time::Duration;
+///
+/// let auto_config = Auto {
+///     server_address: "play.example.com".to_string(),
+///     timeout: Some(Duration::from_secs(10)),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Auto {
+    /// The server address.
+    ///
+    /// This can be either an IP or a hostname, and both may optionally have a
+    /// port at the end. If a port is given, both editions are pinged on it,
+    /// otherwise each edition is pinged on its default port.
+    pub server_address: String,
+    /// The timeout used for both pings.
+    pub timeout: Option<Duration>,
+    /// How long to keep waiting for the other edition after one has answered.
+    pub grace_period: Duration,
+    /// The addresses both pings may connect to, if restricted.
+    pub address_policy: Option<AddressPolicy>,
+    /// The rate limiter both pings have to get past.
+    pub rate_limiter: Option<RateLimiter>,
+}
+
+impl Default for Auto {
+    fn default() -> Self {
+        Self {
+            server_address: String::new(),
+            timeout: None,
+            grace_period: Duration::from_millis(500),
+            address_policy: None,
+            rate_limiter: None,
+        }
+    }
+}
+
+impl Auto {
+    pub(crate) fn java(&self) -> Java {
+        Java {
+            server_address: self.server_address.clone(),
+            timeout: self.timeout,
+            address_policy: self.address_policy.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn bedrock(&self) -> Bedrock {
+        Bedrock {
+            server_address: self.server_address.clone(),
+            timeout: self.timeout,
+            address_policy: self.address_policy.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pingable for Auto {
+    type Response = AutoResponse;
+
+    fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let java = self.java();
+        let bedrock = self.bedrock();
+
+        race(
+            move || java.ping(),
+            move || bedrock.ping(),
+            self.grace_period,
+        )
+    }
+}
+
+/// Run both pings on their own thread, returning once either both have
+/// answered or the grace period after the first success has passed.
+pub(crate) fn race<J, B>(
+    java: J,
+    bedrock: B,
+    grace_period: Duration,
+) -> Result<(Duration, AutoResponse), Error>
+where
+    J: FnOnce() -> Result<(Duration, JavaResponse), Error> + Send + 'static,
+    B: FnOnce() -> Result<(Duration, BedrockResponse), Error> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+
+    let java_tx = tx.clone();
+    thread::spawn(move || {
+        let _ = java_tx.send(Answer::Java(java()));
+    });
+
+    thread::spawn(move || {
+        let _ = tx.send(Answer::Bedrock(bedrock()));
+    });
+
+    let mut answers = Answers::default();
+
+    // Wait for the first answer, and if it was a success give the other
+    // edition a chance to answer as well.
+    if let Ok(answer) = rx.recv() {
+        answers.push(answer);
+    }
+
+    let second = if answers.any_success() {
+        rx.recv_timeout(grace_period).ok()
+    } else {
+        rx.recv().ok()
+    };
+
+    if let Some(answer) = second {
+        answers.push(answer);
+    }
+
+    answers.finish()
+}
+
+/// The responses of the editions that answered an [`Auto`] ping.
+///
+/// At least one edition always answered, so a response without either can't
+/// be built or deserialized.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::AutoResponse;
+///
+/// let json = r#"{"java":null,"bedrock":null}"#;
+/// assert!(serde_json::from_str::<AutoResponse>(json).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum AutoResponse {
+    /// Only the Java status ping was answered.
+    Java(Duration, JavaResponse),
+    /// Only the Bedrock ping was answered.
+    Bedrock(Duration, BedrockResponse),
+    /// Both editions answered, as servers running Geyser do.
+    Both {
+        java: (Duration, JavaResponse),
+        bedrock: (Duration, BedrockResponse),
+    },
+}
+
+impl AutoResponse {
+    /// The latency and response of the Java status ping, if it was answered.
+    pub fn java(&self) -> Option<(Duration, &JavaResponse)> {
+        match self {
+            AutoResponse::Java(latency, response)
+            | AutoResponse::Both {
+                java: (latency, response),
+                ..
+            } => Some((*latency, response)),
+            AutoResponse::Bedrock(..) => None,
+        }
+    }
+
+    /// The latency and response of the Bedrock ping, if it was answered.
+    pub fn bedrock(&self) -> Option<(Duration, &BedrockResponse)> {
+        match self {
+            AutoResponse::Bedrock(latency, response)
+            | AutoResponse::Both {
+                bedrock: (latency, response),
+                ..
+            } => Some((*latency, response)),
+            AutoResponse::Java(..) => None,
+        }
+    }
+
+    /// The lowest latency of the editions that answered.
+    pub fn latency(&self) -> Duration {
+        match self {
+            AutoResponse::Java(latency, _) | AutoResponse::Bedrock(latency, _) => *latency,
+            AutoResponse::Both { java, bedrock } => java.0.min(bedrock.0),
+        }
+    }
+
+    /// Split the response into the answer of each edition.
+    #[allow(clippy::type_complexity)]
+    pub fn into_parts(
+        self,
+    ) -> (
+        Option<(Duration, JavaResponse)>,
+        Option<(Duration, BedrockResponse)>,
+    ) {
+        match self {
+            AutoResponse::Java(latency, response) => (Some((latency, response)), None),
+            AutoResponse::Bedrock(latency, response) => (None, Some((latency, response))),
+            AutoResponse::Both { java, bedrock } => (Some(java), Some(bedrock)),
+        }
+    }
+
+    /// The editions that answered the ping.
+    pub fn editions(&self) -> Vec<Edition> {
+        let mut editions = Vec::new();
+
+        if self.java().is_some() {
+            editions.push(Edition::Java);
+        }
+
+        if self.bedrock().is_some() {
+            editions.push(Edition::Bedrock);
+        }
+
+        editions
+    }
+}
+
+/// Prefers the Java response, as it contains more information.
+impl From<(Duration, AutoResponse)> for ServerStatus {
+    fn from((_, response): (Duration, AutoResponse)) -> Self {
+        match response {
+            AutoResponse::Java(latency, response)
+            | AutoResponse::Both {
+                java: (latency, response),
+                ..
+            } => ServerStatus::from((latency, response)),
+            AutoResponse::Bedrock(latency, response) => ServerStatus::from((latency, response)),
+        }
+    }
+}
+
+pub(crate) enum Answer {
+    Java(Result<(Duration, JavaResponse), Error>),
+    Bedrock(Result<(Duration, BedrockResponse), Error>),
+}
+
+/// Collects the answers of both editions.
+#[derive(Default)]
+pub(crate) struct Answers {
+    java: Option<Result<(Duration, JavaResponse), Error>>,
+    bedrock: Option<Result<(Duration, BedrockResponse), Error>>,
+}
+
+impl Answers {
+    pub(crate) fn push(&mut self, answer: Answer) {
+        match answer {
+            Answer::Java(result) => self.java = Some(result),
+            Answer::Bedrock(result) => self.bedrock = Some(result),
+        }
+    }
+
+    pub(crate) fn any_success(&self) -> bool {
+        matches!(self.java, Some(Ok(_))) || matches!(self.bedrock, Some(Ok(_)))
+    }
+
+    /// Builds the response from the successful answers.
+    ///
+    /// If neither edition answered, the error of the Java ping is returned.
+    pub(crate) fn finish(self) -> Result<(Duration, AutoResponse), Error> {
+        let (java, java_err) = split(self.java);
+        let (bedrock, bedrock_err) = split(self.bedrock);
+
+        let response = match (java, bedrock) {
+            (Some(java), Some(bedrock)) => AutoResponse::Both { java, bedrock },
+            (Some((latency, response)), None) => AutoResponse::Java(latency, response),
+            (None, Some((latency, response))) => AutoResponse::Bedrock(latency, response),
+            (None, None) => {
+                return Err(java_err
+                    .or(bedrock_err)
+                    .unwrap_or_else(|| io::Error::other("no edition answered").into()))
+            }
+        };
+
+        Ok((response.latency(), response))
+    }
+}
+
+fn split<T>(result: Option<Result<T, Error>>) -> (Option<T>, Option<Error>) {
+    match result {
+        Some(Ok(value)) => (Some(value), None),
+        Some(Err(err)) => (None, Some(err)),
+        None => (None, None),
+    }
+}
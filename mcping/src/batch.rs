@@ -0,0 +1,283 @@
+//! Pinging many servers at once.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{Auto, Bedrock, Error, Java, Pingable, ServerStatus};
+
+/// A server of either edition to ping as part of a batch.
+///
+/// Every configuration converts into a `Target` with `From`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Target {
+    Java(Java),
+    Bedrock(Bedrock),
+    Auto(Auto),
+}
+
+impl Target {
+    /// The server address of the configuration.
+    pub fn server_address(&self) -> &str {
+        match self {
+            Target::Java(java) => &java.server_address,
+            Target::Bedrock(bedrock) => &bedrock.server_address,
+            Target::Auto(auto) => &auto.server_address,
+        }
+    }
+
+    /// The host part of the server address, used to limit pings per host.
+    pub(crate) fn host(&self) -> String {
+        let address = self.server_address();
+        address
+            .split(':')
+            .next()
+            .unwrap_or(address)
+            .to_ascii_lowercase()
+    }
+}
+
+impl From<Java> for Target {
+    fn from(java: Java) -> Self {
+        Target::Java(java)
+    }
+}
+
+impl From<Bedrock> for Target {
+    fn from(bedrock: Bedrock) -> Self {
+        Target::Bedrock(bedrock)
+    }
+}
+
+impl From<Auto> for Target {
+    fn from(auto: Auto) -> Self {
+        Target::Auto(auto)
+    }
+}
+
+impl Pingable for Target {
+    type Response = ServerStatus;
+
+    fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let status = match self {
+            Target::Java(java) => crate::get_status_any(java)?,
+            Target::Bedrock(bedrock) => crate::get_status_any(bedrock)?,
+            Target::Auto(auto) => crate::get_status_any(auto)?,
+        };
+
+        Ok((status.latency, status))
+    }
+}
+
+/// Limits on how many pings of a batch run at the same time.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct BatchOptions {
+    /// The maximum number of pings in flight.
+    pub concurrency: usize,
+    /// The maximum number of pings in flight to the same host.
+    pub per_host: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 64,
+            per_host: 2,
+        }
+    }
+}
+
+/// The outcome of pinging one target of a batch.
+#[derive(Debug)]
+pub struct BatchResult {
+    /// The position of the target in the batch.
+    pub index: usize,
+    /// The target that was pinged.
+    pub target: Target,
+    /// The status of the server, or the error that occurred pinging it.
+    pub result: Result<ServerStatus, Error>,
+}
+
+/// Ping every target on a pool of threads, yielding results as they complete.
+///
+/// At most `options.concurrency` threads are spawned, and no more than
+/// `options.per_host` of them ping the same host at once.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{BatchOptions, Bedrock, Java, Target};
+///
+/// let targets: Vec<Target> = vec![
+///     Java {
+///         server_address: "mc.hypixel.net".into(),
+///         ..Default::default()
+///     }
+///     .into(),
+///     Bedrock {
+///         server_address: "play.nethergames.org".into(),
+///         ..Default::default()
+///     }
+///     .into(),
+/// ];
+///
+/// for result in mcping::get_status_batch(targets, BatchOptions::default()) {
+///     match result.result {
+///         Ok(status) => println!("{}: {}ms", result.target.server_address(), status.latency.as_millis()),
+///         Err(e) => println!("{}: {}", result.target.server_address(), e),
+///     }
+/// }
+/// ```
+///
+/// Every Bedrock ping binds its own UDP port, so many can run at once:
+///
+/// ```
+/// use mcping::{BatchOptions, Bedrock};
+/// use std::{net::UdpSocket, thread, time::Duration};
+///
+/// # let server = UdpSocket::bind("127.0.0.1:0")?;
+/// # let server_address = server.local_addr()?.to_string();
+/// # thread::spawn(move || {
+/// #     let mut buf = [0; 64];
+/// #     while let Ok((_, client)) = server.recv_from(&mut buf) {
+/// #         // Answer slowly, so that the pings overlap.
+/// #         thread::sleep(Duration::from_millis(50));
+/// #         let payload = b"MCPE;A server;390;1.14.60;0;10;1;World;Survival;1;19132;19133;";
+/// #         let mut pong = vec![0x1c];
+/// #         pong.extend_from_slice(&[0; 16]);
+/// #         pong.extend_from_slice(&buf[9..25]);
+/// #         pong.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+/// #         pong.extend_from_slice(payload);
+/// #         server.send_to(&pong, client).unwrap();
+/// #     }
+/// # });
+/// let targets = (0..6).map(|_| Bedrock {
+///     server_address: server_address.clone(),
+///     timeout: Some(Duration::from_secs(5)),
+///     tries: 1,
+///     ..Default::default()
+/// });
+/// let options = BatchOptions {
+///     per_host: 6,
+///     ..Default::default()
+/// };
+///
+/// for result in mcping::get_status_batch(targets, options) {
+///     assert_eq!(result.result?.motd.text(), "A server");
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn get_status_batch<I>(targets: I, options: BatchOptions) -> BatchIter
+where
+    I: IntoIterator,
+    I::Item: Into<Target>,
+{
+    let queue: VecDeque<_> = targets.into_iter().map(Into::into).enumerate().collect();
+    let workers = options.concurrency.max(1).min(queue.len());
+
+    let state = Arc::new(State {
+        queue: Mutex::new(Queue {
+            pending: queue,
+            in_flight: HashMap::new(),
+        }),
+        available: Condvar::new(),
+        per_host: options.per_host.max(1),
+    });
+
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..workers {
+        let state = Arc::clone(&state);
+        let tx = tx.clone();
+
+        thread::spawn(move || {
+            while let Some((index, host, target)) = state.next() {
+                let result = target.clone().ping().map(|(_, status)| status);
+                state.done(&host);
+
+                if tx
+                    .send(BatchResult {
+                        index,
+                        target,
+                        result,
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+
+    BatchIter { rx }
+}
+
+/// An iterator over the results of [`get_status_batch`], in the order the
+/// pings complete.
+#[derive(Debug)]
+pub struct BatchIter {
+    rx: mpsc::Receiver<BatchResult>,
+}
+
+impl Iterator for BatchIter {
+    type Item = BatchResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rx.recv().ok()
+    }
+}
+
+struct State {
+    queue: Mutex<Queue>,
+    available: Condvar,
+    per_host: usize,
+}
+
+struct Queue {
+    pending: VecDeque<(usize, Target)>,
+    in_flight: HashMap<String, usize>,
+}
+
+impl State {
+    /// Takes the next target whose host isn't at its limit, waiting for one to
+    /// become available.
+    fn next(&self) -> Option<(usize, String, Target)> {
+        let mut queue = self.queue.lock().unwrap();
+
+        loop {
+            if queue.pending.is_empty() {
+                return None;
+            }
+
+            let Queue { pending, in_flight } = &mut *queue;
+            let position = pending.iter().position(|(_, target)| {
+                in_flight.get(&target.host()).copied().unwrap_or(0) < self.per_host
+            });
+
+            if let Some((index, target)) = position.and_then(|i| pending.remove(i)) {
+                let host = target.host();
+                *in_flight.entry(host.clone()).or_insert(0) += 1;
+                return Some((index, host, target));
+            }
+
+            queue = self.available.wait(queue).unwrap();
+        }
+    }
+
+    fn done(&self, host: &str) {
+        let mut queue = self.queue.lock().unwrap();
+
+        if let Some(count) = queue.in_flight.get_mut(host) {
+            *count -= 1;
+
+            if *count == 0 {
+                queue.in_flight.remove(host);
+            }
+        }
+
+        self.available.notify_all();
+    }
+}
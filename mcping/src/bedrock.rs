@@ -1,15 +1,19 @@
 //! Implementation of the RakNet ping/pong protocol.
 //! https://wiki.vg/Raknet_Protocol#Unconnected_Ping
 
-use crate::{Error, Pingable};
+use crate::{
+    connect::{self, Net},
+    AddressPolicy, Error, PingMetadata, Pingable, RateLimiter,
+};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use serde::{Deserialize, Serialize};
 use std::{
+    hash::{Hash, Hasher},
     io::{self, Cursor, Read},
     net::{Ipv4Addr, SocketAddr, UdpSocket},
     thread,
     time::{Duration, Instant},
 };
-use trust_dns_resolver::{config::*, Resolver};
 
 /// Raknets default OFFLINE_MESSAGE_DATA_ID.
 ///
@@ -58,11 +62,19 @@ pub struct Bedrock {
     /// The amount of times to try to send the ping packet.
     ///
     /// In case of packet loss an attempt can be made to send more than a single ping.
+    /// To retry the whole ping instead, see [`Retry`](crate::Retry).
     pub tries: usize,
     /// The amount of time to wait in-between sending ping packets.
     pub wait_to_try: Option<Duration>,
     /// The socket addresses to try binding the UDP socket to.
+    ///
+    /// By default the system picks a free port, so any number of pings can
+    /// run at once.
     pub socket_addresses: Vec<SocketAddr>,
+    /// The addresses the ping may be sent to, if restricted.
+    pub address_policy: Option<AddressPolicy>,
+    /// The rate limiter the ping has to get past before sending anything.
+    pub rate_limiter: Option<RateLimiter>,
 }
 
 impl Default for Bedrock {
@@ -72,11 +84,9 @@ impl Default for Bedrock {
             timeout: None,
             tries: 5,
             wait_to_try: Some(Duration::from_millis(10)),
-            socket_addresses: vec![
-                SocketAddr::from((Ipv4Addr::new(0, 0, 0, 0), 25567)),
-                SocketAddr::from((Ipv4Addr::new(0, 0, 0, 0), 25568)),
-                SocketAddr::from((Ipv4Addr::new(0, 0, 0, 0), 25569)),
-            ],
+            socket_addresses: vec![SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))],
+            address_policy: None,
+            rate_limiter: None,
         }
     }
 }
@@ -84,10 +94,29 @@ impl Default for Bedrock {
 impl Pingable for Bedrock {
     type Response = BedrockResponse;
 
-    fn ping(self) -> Result<(u64, Self::Response), Error> {
-        let mut connection =
-            Connection::new(&self.server_address, &self.socket_addresses, self.timeout)?;
+    fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let resolver = connect::resolver()?;
+
+        self.ping_with(&Net {
+            resolver: &resolver,
+            bind_address: None,
+            proxy: None,
+            address_policy: self.address_policy.as_ref(),
+            rate_limiter: self.rate_limiter.as_ref(),
+        })
+    }
+}
+
+impl Bedrock {
+    pub(crate) fn ping_with(&self, net: &Net) -> Result<(Duration, BedrockResponse), Error> {
+        let mut connection = Connection::new(
+            net,
+            &self.server_address,
+            &self.socket_addresses,
+            self.timeout,
+        )?;
 
+        let sent = Instant::now();
         for _ in 0..self.tries {
             connection.send(Packet::UnconnectedPing)?;
 
@@ -98,28 +127,27 @@ impl Pingable for Bedrock {
 
         let before = Instant::now();
         if let Packet::UnconnectedPong { payload, .. } = connection.read()? {
-            let latency = (Instant::now() - before).as_millis() as u64;
+            let latency = before.elapsed();
 
             // Attempt to extract useful information from the payload.
-            if let Some(response) = BedrockResponse::extract(&payload) {
+            if let Some(mut response) = BedrockResponse::extract(&payload) {
+                connection.metadata.status = sent.elapsed();
+                response.metadata = Some(connection.metadata);
                 Ok((latency, response))
             } else {
-                Err(Error::IoError(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Invalid Payload",
-                )))
+                Err(Error::IoError(io::Error::other("Invalid Payload")))
             }
         } else {
-            Err(Error::IoError(io::Error::new(
-                io::ErrorKind::Other,
-                "Invalid Packet Response",
-            )))
+            Err(Error::IoError(io::Error::other("Invalid Packet Response")))
         }
     }
 }
 
 /// Represents the edition of a bedrock server.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+///
+/// This is serialized as the edition string sent by the server (ex: MCPE).
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
 pub enum BedrockEdition {
     PocketEdition,
     EducationEdition,
@@ -147,10 +175,19 @@ impl From<String> for BedrockEdition {
     }
 }
 
+impl From<BedrockEdition> for String {
+    fn from(edition: BedrockEdition) -> Self {
+        edition.to_string()
+    }
+}
+
 /// Bedrock Server Payload Response
 ///
+/// The payload sent by the server can be recreated with
+/// [`BedrockResponse::to_payload`].
+///
 /// See More: https://wiki.vg/Raknet_Protocol#Unconnected_Pong
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BedrockResponse {
     /// The server's edition.
     pub edition: BedrockEdition,
@@ -171,8 +208,10 @@ pub struct BedrockResponse {
     pub players_online: Option<i64>,
     /// The maximum number of players that could be online at once.
     pub players_max: Option<i64>,
-    /// The server UUID.
-    pub server_id: Option<i64>,
+    /// The server GUID, an unsigned 64-bit integer.
+    ///
+    /// IDs sent as negative numbers by some server software keep their bits.
+    pub server_id: Option<u64>,
     /// The second line of the server's MOTD.
     ///
     /// In practice, it looks like servers don't really use this. It seems to get
@@ -187,6 +226,48 @@ pub struct BedrockResponse {
     pub port_v4: Option<u16>,
     /// The port to connect to the server on with an IPv6 address.
     pub port_v6: Option<u16>,
+    /// How the ping that received this response went.
+    ///
+    /// This is not part of the payload, so it is ignored when comparing
+    /// responses.
+    #[serde(skip)]
+    pub metadata: Option<PingMetadata>,
+}
+
+impl PartialEq for BedrockResponse {
+    fn eq(&self, other: &Self) -> bool {
+        self.edition == other.edition
+            && self.motd_1 == other.motd_1
+            && self.protocol_version == other.protocol_version
+            && self.version_name == other.version_name
+            && self.players_online == other.players_online
+            && self.players_max == other.players_max
+            && self.server_id == other.server_id
+            && self.motd_2 == other.motd_2
+            && self.game_mode == other.game_mode
+            && self.game_mode_id == other.game_mode_id
+            && self.port_v4 == other.port_v4
+            && self.port_v6 == other.port_v6
+    }
+}
+
+impl Eq for BedrockResponse {}
+
+impl Hash for BedrockResponse {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.edition.hash(state);
+        self.motd_1.hash(state);
+        self.protocol_version.hash(state);
+        self.version_name.hash(state);
+        self.players_online.hash(state);
+        self.players_max.hash(state);
+        self.server_id.hash(state);
+        self.motd_2.hash(state);
+        self.game_mode.hash(state);
+        self.game_mode_id.hash(state);
+        self.port_v4.hash(state);
+        self.port_v6.hash(state);
+    }
 }
 
 impl BedrockResponse {
@@ -204,7 +285,7 @@ impl BedrockResponse {
     /// Game mode (numeric)
     /// Port (IPv4)
     /// Port (IPv6)
-    pub(crate) fn extract(payload: &str) -> Option<Self> {
+    pub fn extract(payload: &str) -> Option<Self> {
         let mut parts = payload.split(';').map(|s| s.to_string());
 
         Some(BedrockResponse {
@@ -214,14 +295,79 @@ impl BedrockResponse {
             version_name: parts.next()?,
             players_online: parts.next().and_then(|s| s.parse().ok()),
             players_max: parts.next().and_then(|s| s.parse().ok()),
-            server_id: parts.next().and_then(|s| s.parse().ok()),
+            server_id: parts.next().and_then(|s| parse_server_id(&s)),
             motd_2: parts.next(),
             game_mode: parts.next(),
             game_mode_id: parts.next().and_then(|s| s.parse().ok()),
             port_v4: parts.next().and_then(|s| s.parse().ok()),
             port_v6: parts.next().and_then(|s| s.parse().ok()),
+            metadata: None,
         })
     }
+
+    /// Builds the semicolon-separated payload that this response was extracted
+    /// from.
+    ///
+    /// Optional fields are written up to the last one that is present, so
+    /// [`BedrockResponse::extract`] returns an identical response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::BedrockResponse;
+    ///
+    /// let payload = "MCPE;Dedicated Server;390;1.14.60;0;10;13253860892328930865;\
+    ///                Bedrock level;Survival;1;19132;19133;";
+    /// let response = BedrockResponse::extract(payload).unwrap();
+    ///
+    /// assert_eq!(response.server_id, Some(13253860892328930865));
+    /// assert_eq!(response.to_payload(), payload);
+    /// ```
+    pub fn to_payload(&self) -> String {
+        fn opt<T: ToString>(value: &Option<T>) -> Option<String> {
+            value.as_ref().map(T::to_string)
+        }
+
+        let mut parts = vec![
+            self.edition.to_string(),
+            self.motd_1.clone(),
+            opt(&self.protocol_version).unwrap_or_default(),
+            self.version_name.clone(),
+        ];
+
+        let optional = [
+            opt(&self.players_online),
+            opt(&self.players_max),
+            opt(&self.server_id),
+            self.motd_2.clone(),
+            self.game_mode.clone(),
+            opt(&self.game_mode_id),
+            opt(&self.port_v4),
+            opt(&self.port_v6),
+        ];
+
+        if let Some(last) = optional.iter().rposition(Option::is_some) {
+            parts.extend(
+                optional[..=last]
+                    .iter()
+                    .map(|part| part.clone().unwrap_or_default()),
+            );
+        }
+
+        // Servers terminate a full payload with a semicolon.
+        if self.port_v6.is_some() {
+            parts.push(String::new());
+        }
+
+        parts.join(";")
+    }
+}
+
+/// Parse a server ID, whether it was sent unsigned or signed.
+fn parse_server_id(id: &str) -> Option<u64> {
+    id.parse()
+        .ok()
+        .or_else(|| id.parse::<i64>().ok().map(|id| id as u64))
 }
 
 /// Extension to `Read` and `ReadBytesExt` that supplies simple methods to write RakNet types.
@@ -233,8 +379,7 @@ trait ReadBedrockExt: Read + ReadBytesExt {
         let len = self.read_u16::<BigEndian>()?;
         let mut buf = vec![0; len as usize];
         self.read_exact(&mut buf)?;
-        String::from_utf8(buf)
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Invalid UTF-8 String."))
+        String::from_utf8(buf).map_err(|_| io::Error::other("Invalid UTF-8 String."))
     }
 }
 
@@ -244,6 +389,7 @@ impl<T: Read + ReadBytesExt> ReadBedrockExt for T {}
 #[derive(Debug)]
 pub(crate) enum Packet {
     UnconnectedPing,
+    #[allow(dead_code)]
     UnconnectedPong {
         time: u64,
         server_id: u64,
@@ -254,39 +400,37 @@ pub(crate) enum Packet {
 /// Udp Socket Connection to a Raknet Bedrock Server.
 struct Connection {
     socket: UdpSocket,
+    metadata: PingMetadata,
 }
 
 impl Connection {
     fn new(
+        net: &Net,
         address: &str,
         socket_addresses: &[SocketAddr],
         timeout: Option<Duration>,
     ) -> Result<Self, Error> {
-        let mut parts = address.split(':');
-
-        let host = parts.next().ok_or(Error::InvalidAddress)?.to_string();
-
-        let port = if let Some(port) = parts.next() {
-            port.parse::<u16>().map_err(|_| Error::InvalidAddress)?
-        } else {
-            DEFAULT_PORT
-        };
+        let (host, port) = connect::parse_address(address, DEFAULT_PORT)?;
 
         // Do a hostname lookup
-        let resolver = Resolver::new(ResolverConfig::default(), ResolverOpts::default()).unwrap();
+        let resolved = connect::lookup_bedrock(net.resolver, &host, port)?;
+
+        net.check_address(resolved.addr)?;
 
-        let ip = resolver
-            .lookup_ip(host.as_str())
-            .ok()
-            .and_then(|ips| ips.iter().next())
-            .ok_or(Error::DnsLookupFailed)?;
+        if let Some(limiter) = net.rate_limiter {
+            limiter.wait(&host, resolved.addr.ip())?;
+        }
 
+        let start = Instant::now();
         let socket = UdpSocket::bind(socket_addresses)?;
-        socket.connect((ip, port))?;
+        socket.connect(resolved.addr)?;
         socket.set_read_timeout(timeout)?;
         socket.set_write_timeout(timeout)?;
 
-        Ok(Self { socket })
+        Ok(Self {
+            socket,
+            metadata: resolved.metadata(start.elapsed()),
+        })
     }
 
     fn send(&mut self, packet: Packet) -> Result<(), io::Error> {
@@ -299,12 +443,7 @@ impl Connection {
 
                 self.socket.send(&buf)?;
             }
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Invalid C -> S Packet",
-                ))
-            }
+            _ => return Err(io::Error::other("Invalid C -> S Packet")),
         }
 
         Ok(())
@@ -326,8 +465,7 @@ impl Connection {
                 buf.read_exact(&mut tmp)?;
 
                 if tmp != OFFLINE_MESSAGE_DATA_ID {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
+                    return Err(io::Error::other(
                         "incorrect offline message data ID received",
                     ));
                 }
@@ -340,10 +478,7 @@ impl Connection {
                     payload,
                 })
             }
-            _ => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Invalid S -> C Packet",
-            )),
+            _ => Err(io::Error::other("Invalid S -> C Packet")),
         }
     }
 }
@@ -0,0 +1,587 @@
+//! Caching responses so that servers pinged often are pinged less.
+
+use std::{
+    any::{Any, TypeId},
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    auto::Auto, batch::Target, bedrock, java, Bedrock, Error, Java, LatencyProbe, Pingable,
+};
+
+/// How long a [`ResponseCache`] keeps responses and errors.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::CacheOptions;
+/// use std::time::Duration;
+///
+/// let options = CacheOptions {
+///     ttl: Duration::from_secs(60),
+///     stale_while_revalidate: Some(Duration::from_secs(300)),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheOptions {
+    /// How long a response is returned without pinging the server again.
+    pub ttl: Duration,
+    /// How long a failed ping is remembered, if at all.
+    ///
+    /// While it is, pinging the server returns the same error without trying
+    /// again.
+    pub negative_ttl: Option<Duration>,
+    /// How long past its `ttl` a response may still be returned.
+    ///
+    /// The first time an expired response is returned, the server is pinged in
+    /// the background so the next caller gets a fresh response. If that ping
+    /// fails, the expired response is still returned until it is too old.
+    pub stale_while_revalidate: Option<Duration>,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(30),
+            negative_ttl: Some(Duration::from_secs(5)),
+            stale_while_revalidate: None,
+        }
+    }
+}
+
+/// Identifies the server a configuration pings, for caching.
+pub trait CacheKey {
+    /// The edition and normalized address of the server, and the settings
+    /// that change the outcome of pinging it.
+    ///
+    /// Configurations that ping the same server the same way must return the
+    /// same key, however their addresses are written. Configurations with a
+    /// different [`AddressPolicy`](crate::AddressPolicy) must not, so that a
+    /// response cached for one is never returned to the other.
+    fn cache_key(&self) -> Result<String, Error>;
+}
+
+impl CacheKey for Java {
+    fn cache_key(&self) -> Result<String, Error> {
+        let settings = (&self.address_policy, self.timeout, self.lenient);
+        key("java", &self.server_address, java::DEFAULT_PORT, settings)
+    }
+}
+
+impl CacheKey for Bedrock {
+    fn cache_key(&self) -> Result<String, Error> {
+        let settings = (&self.address_policy, self.timeout);
+        key(
+            "bedrock",
+            &self.server_address,
+            bedrock::DEFAULT_PORT,
+            settings,
+        )
+    }
+}
+
+impl CacheKey for Auto {
+    fn cache_key(&self) -> Result<String, Error> {
+        // Without a port each edition uses its own, so the Java default is as
+        // good as any for telling addresses apart.
+        let settings = (&self.address_policy, self.timeout);
+        key("auto", &self.server_address, java::DEFAULT_PORT, settings)
+    }
+}
+
+impl CacheKey for LatencyProbe {
+    fn cache_key(&self) -> Result<String, Error> {
+        self.java.cache_key()
+    }
+}
+
+impl CacheKey for Target {
+    fn cache_key(&self) -> Result<String, Error> {
+        match self {
+            Target::Java(java) => java.cache_key(),
+            Target::Bedrock(bedrock) => bedrock.cache_key(),
+            Target::Auto(auto) => auto.cache_key(),
+        }
+    }
+}
+
+fn key(
+    edition: &str,
+    address: &str,
+    default_port: u16,
+    settings: impl Hash,
+) -> Result<String, Error> {
+    let mut hasher = DefaultHasher::new();
+    settings.hash(&mut hasher);
+
+    Ok(format!(
+        "{}/{}/{:016x}",
+        edition,
+        crate::connect::normalize_address(address, default_port)?,
+        hasher.finish()
+    ))
+}
+
+/// A cache of responses, shared by every clone of it.
+///
+/// Responses of every type of configuration can be kept in the same cache.
+/// While a server is being pinged, other callers asking for it wait for that
+/// ping instead of sending their own.
+///
+/// See [`Cached`] for pinging through a cache.
+#[derive(Clone)]
+pub struct ResponseCache {
+    inner: Arc<Inner>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache.
+    pub fn new(options: CacheOptions) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                options,
+                state: Mutex::new(State {
+                    entries: HashMap::new(),
+                    in_flight: HashMap::new(),
+                    sweep_at: 64,
+                }),
+            }),
+        }
+    }
+
+    /// The options the cache was created with.
+    pub fn options(&self) -> &CacheOptions {
+        &self.inner.options
+    }
+
+    /// Forget every cached response and error.
+    ///
+    /// Pings in flight are unaffected.
+    pub fn clear(&self) {
+        self.inner.state.lock().unwrap().entries.clear();
+    }
+
+    /// Return the cached outcome for `key`, or call `ping` and cache its
+    /// outcome.
+    ///
+    /// Along with the response, `ping` returns something only its own caller
+    /// gets, such as the attempts it made. Callers answered by the cache get
+    /// the default instead.
+    pub(crate) fn get_or_ping<R, A, F>(
+        &self,
+        key: String,
+        ping: F,
+    ) -> Result<((Duration, R), A), Error>
+    where
+        R: Clone + Send + Sync + 'static,
+        A: Default,
+        F: FnOnce() -> Result<((Duration, R), A), Error> + Send + 'static,
+    {
+        let outcome = match self.lookup::<R>(key) {
+            Lookup::Fresh(outcome) => outcome,
+            Lookup::Stale(outcome, refresh) => {
+                if let Some(pending) = refresh {
+                    thread::spawn(move || {
+                        pending.complete(ping().map(|(value, _)| erase(value)).map_err(Arc::new))
+                    });
+                }
+                outcome
+            }
+            Lookup::Wait(flight) => flight.wait(),
+            Lookup::Miss(pending) => match ping() {
+                Ok((value, own)) => {
+                    pending.complete(Ok(erase(value.clone())));
+                    return Ok((value, own));
+                }
+                Err(err) => {
+                    pending.complete(Err(Arc::new(err.duplicate())));
+                    return Err(err);
+                }
+            },
+        };
+
+        restore(outcome).map(|value| (value, A::default()))
+    }
+
+    pub(crate) fn lookup<R: 'static>(&self, key: String) -> Lookup {
+        let key = (TypeId::of::<R>(), key);
+        let options = &self.inner.options;
+        let mut state = self.inner.state.lock().unwrap();
+
+        if let Some(entry) = state.entries.get(&key) {
+            let age = entry.stored.elapsed();
+
+            match &entry.outcome {
+                Ok(_) if age < options.ttl => return Lookup::Fresh(entry.outcome.clone()),
+                Err(_) if options.negative_ttl.is_some_and(|ttl| age < ttl) => {
+                    return Lookup::Fresh(entry.outcome.clone())
+                }
+                Ok(_)
+                    if options
+                        .stale_while_revalidate
+                        .is_some_and(|stale| age < options.ttl + stale) =>
+                {
+                    let outcome = entry.outcome.clone();
+
+                    // Only the first caller to see the stale response refreshes it.
+                    let refresh = if state.in_flight.contains_key(&key) {
+                        None
+                    } else {
+                        Some(self.start(&mut state, key))
+                    };
+
+                    return Lookup::Stale(outcome, refresh);
+                }
+                _ => {}
+            }
+        }
+
+        match state.in_flight.get(&key) {
+            Some(flight) => Lookup::Wait(Arc::clone(flight)),
+            None => Lookup::Miss(self.start(&mut state, key)),
+        }
+    }
+
+    fn start(&self, state: &mut State, key: Key) -> Pending {
+        let flight = Arc::new(Flight::new());
+        state.in_flight.insert(key.clone(), Arc::clone(&flight));
+
+        Pending {
+            cache: self.clone(),
+            key,
+            flight,
+            done: false,
+        }
+    }
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("options", &self.inner.options)
+            .finish()
+    }
+}
+
+/// Configuration for pinging through a [`ResponseCache`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{CacheOptions, Cached, Java, ResponseCache};
+///
+/// let cache = ResponseCache::new(CacheOptions::default());
+///
+/// for _ in 0..10 {
+///     // Only the first ping reaches the server.
+///     let (latency, response) = mcping::get_status(Cached {
+///         pingable: Java {
+///             server_address: "mc.hypixel.net".into(),
+///             ..Default::default()
+///         },
+///         cache: cache.clone(),
+///     })?;
+/// }
+/// # Ok::<(), mcping::Error>(())
+/// ```
+///
+/// The caller that pinged the server gets its error as-is, and later callers
+/// get it as [`Error::Cached`]. Configurations with different settings, such
+/// as an address policy, don't share cached outcomes:
+///
+/// ```
+/// use mcping::{AddressPolicy, CacheOptions, Cached, Error, ErrorKind, Java, ResponseCache};
+///
+/// let cache = ResponseCache::new(CacheOptions::default());
+/// let ping = |java: Java| {
+///     mcping::get_status(Cached {
+///         pingable: java,
+///         cache: cache.clone(),
+///     })
+/// };
+///
+/// let open = Java {
+///     server_address: "127.0.0.1:1".into(),
+///     ..Default::default()
+/// };
+/// let restricted = Java {
+///     address_policy: Some(AddressPolicy::default()),
+///     ..open.clone()
+/// };
+///
+/// assert_eq!(ping(open).unwrap_err().kind(), ErrorKind::ConnectionRefused);
+/// assert!(matches!(
+///     ping(restricted.clone()),
+///     Err(Error::AddressBlocked { .. })
+/// ));
+/// assert!(matches!(ping(restricted), Err(Error::Cached(_))));
+/// ```
+///
+/// An expired response keeps being returned while refreshing it fails:
+///
+/// ```
+/// use mcping::{CacheKey, CacheOptions, Cached, Error, Pingable, ResponseCache};
+/// use std::{
+///     sync::{
+///         atomic::{AtomicBool, Ordering},
+///         Arc,
+///     },
+///     thread,
+///     time::Duration,
+/// };
+///
+/// #[derive(Clone)]
+/// struct Flaky(Arc<AtomicBool>);
+///
+/// impl Pingable for Flaky {
+///     type Response = String;
+///
+///     fn ping(self) -> Result<(Duration, String), Error> {
+///         if self.0.load(Ordering::SeqCst) {
+///             Ok((Duration::from_millis(20), "up".to_string()))
+///         } else {
+///             Err(Error::DnsLookupFailed)
+///         }
+///     }
+/// }
+///
+/// impl CacheKey for Flaky {
+///     fn cache_key(&self) -> Result<String, Error> {
+///         Ok("flaky".to_string())
+///     }
+/// }
+///
+/// let cache = ResponseCache::new(CacheOptions {
+///     ttl: Duration::ZERO,
+///     negative_ttl: Some(Duration::from_secs(60)),
+///     stale_while_revalidate: Some(Duration::from_secs(60)),
+/// });
+/// let up = Arc::new(AtomicBool::new(true));
+/// let ping = || {
+///     mcping::get_status(Cached {
+///         pingable: Flaky(Arc::clone(&up)),
+///         cache: cache.clone(),
+///     })
+/// };
+///
+/// assert_eq!(ping()?.1, "up");
+///
+/// up.store(false, Ordering::SeqCst);
+/// for _ in 0..3 {
+///     // Each call refreshes the response in the background, which fails.
+///     assert_eq!(ping()?.1, "up");
+///     thread::sleep(Duration::from_millis(50));
+/// }
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cached<P> {
+    /// The configuration used when the cache can't answer.
+    pub pingable: P,
+    /// The cache to answer from.
+    pub cache: ResponseCache,
+}
+
+impl<P> Pingable for Cached<P>
+where
+    P: Pingable + CacheKey + Send + 'static,
+    P::Response: Clone + Send + Sync + 'static,
+{
+    type Response = P::Response;
+
+    fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let key = self.pingable.cache_key()?;
+        let pingable = self.pingable;
+
+        self.cache
+            .get_or_ping(key, move || pingable.ping().map(|value| (value, ())))
+            .map(|(value, ())| value)
+    }
+}
+
+type Key = (TypeId, String);
+type Value = Arc<dyn Any + Send + Sync>;
+
+/// The outcome of a ping, as stored in the cache.
+pub(crate) type Outcome = Result<(Duration, Value), Arc<Error>>;
+
+pub(crate) fn erase<R: Send + Sync + 'static>(
+    (latency, response): (Duration, R),
+) -> (Duration, Value) {
+    (latency, Arc::new(response))
+}
+
+pub(crate) fn restore<R: Clone + 'static>(outcome: Outcome) -> Result<(Duration, R), Error> {
+    match outcome {
+        Ok((latency, value)) => Ok((
+            latency,
+            value
+                .downcast_ref::<R>()
+                .expect("cache entries are keyed by their type")
+                .clone(),
+        )),
+        Err(err) => Err(Error::Cached(err)),
+    }
+}
+
+struct Inner {
+    options: CacheOptions,
+    state: Mutex<State>,
+}
+
+struct State {
+    entries: HashMap<Key, Entry>,
+    in_flight: HashMap<Key, Arc<Flight>>,
+    /// The size at which expired entries are next swept out.
+    sweep_at: usize,
+}
+
+struct Entry {
+    stored: Instant,
+    outcome: Outcome,
+}
+
+/// What the cache knows about a key.
+pub(crate) enum Lookup {
+    /// A response or error to return as-is.
+    Fresh(Outcome),
+    /// An expired response to return, and the ping to refresh it with if
+    /// nobody else is.
+    Stale(Outcome, Option<Pending>),
+    /// Another caller is pinging the server.
+    Wait(Arc<Flight>),
+    /// Nothing is known, so the caller has to ping the server.
+    Miss(Pending),
+}
+
+/// A ping the holder has to make and complete.
+///
+/// If it is dropped without completing, for example because a future was
+/// cancelled or the ping panicked, the callers waiting on it get an error.
+pub(crate) struct Pending {
+    cache: ResponseCache,
+    key: Key,
+    flight: Arc<Flight>,
+    done: bool,
+}
+
+impl Pending {
+    /// Cache the result of the ping, and hand it to everyone waiting for it.
+    pub(crate) fn complete(mut self, outcome: Outcome) {
+        self.done = true;
+        self.finish(outcome, true);
+    }
+
+    fn finish(&self, outcome: Outcome, store: bool) {
+        let cache = &self.cache.inner;
+        let mut state = cache.state.lock().unwrap();
+
+        state.in_flight.remove(&self.key);
+
+        let keep = match &outcome {
+            Ok(_) => true,
+            // A failed refresh leaves the stale response to be served until it
+            // expires.
+            Err(_) => {
+                cache.options.negative_ttl.is_some()
+                    && !state.entries.get(&self.key).is_some_and(|entry| {
+                        entry.outcome.is_ok() && !entry.expired(&cache.options)
+                    })
+            }
+        };
+
+        if store && keep {
+            if state.entries.len() >= state.sweep_at {
+                let options = cache.options;
+                state.entries.retain(|_, entry| !entry.expired(&options));
+                state.sweep_at = (state.entries.len() * 2).max(64);
+            }
+
+            state.entries.insert(
+                self.key.clone(),
+                Entry {
+                    stored: Instant::now(),
+                    outcome: outcome.clone(),
+                },
+            );
+        }
+
+        drop(state);
+        self.flight.finish(outcome);
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if !self.done {
+            let err = std::io::Error::other("the ping was abandoned");
+            self.finish(Err(Arc::new(err.into())), false);
+        }
+    }
+}
+
+impl Entry {
+    fn expired(&self, options: &CacheOptions) -> bool {
+        let age = self.stored.elapsed();
+
+        match self.outcome {
+            Ok(_) => age >= options.ttl + options.stale_while_revalidate.unwrap_or_default(),
+            Err(_) => options.negative_ttl.is_none_or(|ttl| age >= ttl),
+        }
+    }
+}
+
+/// A ping in progress, which other callers can wait on.
+pub(crate) struct Flight {
+    outcome: Mutex<Option<Outcome>>,
+    done: Condvar,
+    #[cfg(feature = "tokio-runtime")]
+    pub(crate) watch: (
+        tokio::sync::watch::Sender<bool>,
+        tokio::sync::watch::Receiver<bool>,
+    ),
+}
+
+impl Flight {
+    fn new() -> Self {
+        Self {
+            outcome: Mutex::new(None),
+            done: Condvar::new(),
+            #[cfg(feature = "tokio-runtime")]
+            watch: tokio::sync::watch::channel(false),
+        }
+    }
+
+    fn finish(&self, outcome: Outcome) {
+        *self.outcome.lock().unwrap() = Some(outcome);
+        self.done.notify_all();
+
+        #[cfg(feature = "tokio-runtime")]
+        let _ = self.watch.0.send(true);
+    }
+
+    /// The outcome, if the ping has finished.
+    #[cfg(feature = "tokio-runtime")]
+    pub(crate) fn outcome(&self) -> Option<Outcome> {
+        self.outcome.lock().unwrap().clone()
+    }
+
+    /// Block until the ping has finished.
+    pub(crate) fn wait(&self) -> Outcome {
+        let mut outcome = self.outcome.lock().unwrap();
+
+        loop {
+            if let Some(outcome) = &*outcome {
+                return outcome.clone();
+            }
+
+            outcome = self.done.wait(outcome).unwrap();
+        }
+    }
+}
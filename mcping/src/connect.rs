@@ -0,0 +1,350 @@
+//! Address parsing, DNS resolution and connection setup shared by both
+//! editions.
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{self, Read, Write},
+    net::{IpAddr, SocketAddr, TcpStream},
+    time::{Duration, Instant},
+};
+use trust_dns_resolver::{config::*, Resolver};
+
+use crate::{AddressPolicy, Error, LatencySource, PingMetadata, RateLimiter};
+
+/// A proxy to open Java connections through.
+///
+/// Bedrock pings are sent over UDP and are never proxied.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Proxy {
+    /// A SOCKS5 proxy, optionally authenticating with a username and password.
+    Socks5 {
+        address: SocketAddr,
+        credentials: Option<(String, String)>,
+    },
+}
+
+/// How connections are made, beyond the configuration of a single ping.
+pub(crate) struct Net<'a> {
+    pub(crate) resolver: &'a Resolver,
+    /// The local address to bind TCP connections to.
+    pub(crate) bind_address: Option<IpAddr>,
+    pub(crate) proxy: Option<&'a Proxy>,
+    pub(crate) address_policy: Option<&'a AddressPolicy>,
+    pub(crate) rate_limiter: Option<&'a RateLimiter>,
+}
+
+impl Net<'_> {
+    /// Make sure the address policy allows connecting to `addr`.
+    pub(crate) fn check_address(&self, addr: SocketAddr) -> Result<(), Error> {
+        match self.address_policy {
+            Some(policy) => policy
+                .check(addr)
+                .map_err(|reason| Error::AddressBlocked { addr, reason }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Split an address up into its host and port, using `default_port` if no port
+/// is given.
+pub(crate) fn parse_address(address: &str, default_port: u16) -> Result<(String, u16), Error> {
+    let mut parts = address.split(':');
+
+    let host = parts.next().ok_or(Error::InvalidAddress)?.to_string();
+
+    let port = if let Some(port) = parts.next() {
+        port.parse::<u16>().map_err(|_| Error::InvalidAddress)?
+    } else {
+        default_port
+    };
+
+    Ok((host, port))
+}
+
+/// The address in a form that is the same for every way of writing it, used
+/// to identify a server.
+pub(crate) fn normalize_address(address: &str, default_port: u16) -> Result<String, Error> {
+    let (host, port) = parse_address(address, default_port)?;
+    Ok(format!("{}:{}", host.to_ascii_lowercase(), port))
+}
+
+pub(crate) fn resolver() -> Result<Resolver, Error> {
+    Ok(Resolver::new(
+        ResolverConfig::default(),
+        ResolverOpts::default(),
+    )?)
+}
+
+/// The address of a server, and how it was found.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Resolved {
+    pub(crate) addr: SocketAddr,
+    pub(crate) srv_used: bool,
+    pub(crate) srv_lookup: Option<Duration>,
+    pub(crate) dns_lookup: Option<Duration>,
+}
+
+impl Resolved {
+    /// An address that didn't need resolving.
+    pub(crate) fn ip(ip: IpAddr, port: u16) -> Self {
+        Self {
+            addr: SocketAddr::new(ip, port),
+            srv_used: false,
+            srv_lookup: None,
+            dns_lookup: None,
+        }
+    }
+
+    /// The metadata of a ping to this address, with the phases after
+    /// connecting left at zero.
+    pub(crate) fn metadata(&self, connect: Duration) -> PingMetadata {
+        PingMetadata {
+            ip: self.addr.ip(),
+            port: self.addr.port(),
+            srv_used: self.srv_used,
+            srv_lookup: self.srv_lookup,
+            dns_lookup: self.dns_lookup,
+            connect,
+            status: Duration::ZERO,
+            pong: None,
+            latency_source: LatencySource::Pong,
+        }
+    }
+}
+
+/// Find the address of a Java server.
+///
+/// - Lookup the SRV record for the domain, if it exists perform a lookup of the ip from the target
+///   and grab the port pointed at by the record.
+///
+///   Note: trust_dns_resolver should do a recursive lookup for an ip but it doesn't seem to at
+///   the moment.
+///
+/// - If the above failed in any way fall back to the normal ip lookup from the host provided
+///   and use the provided port.
+pub(crate) fn lookup_java(resolver: &Resolver, host: &str, port: u16) -> Result<Resolved, Error> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(Resolved::ip(ip, port));
+    }
+
+    let lookup_ip =
+        |host: &str| -> Option<IpAddr> { resolver.lookup_ip(host).ok()?.into_iter().next() };
+
+    let start = Instant::now();
+    let record = resolver
+        .srv_lookup(format!("_minecraft._tcp.{}.", host))
+        .ok()
+        .and_then(|lookup| lookup.into_iter().next());
+    let srv_lookup = Some(start.elapsed());
+
+    let start = Instant::now();
+    let srv = record.and_then(|record| {
+        let ip = lookup_ip(&record.target().to_string())?;
+        Some(SocketAddr::new(ip, record.port()))
+    });
+
+    let (addr, srv_used) = match srv {
+        Some(addr) => (addr, true),
+        None => (
+            SocketAddr::new(lookup_ip(host).ok_or(Error::DnsLookupFailed)?, port),
+            false,
+        ),
+    };
+
+    Ok(Resolved {
+        addr,
+        srv_used,
+        srv_lookup,
+        dns_lookup: Some(start.elapsed()),
+    })
+}
+
+/// Find the address of a Bedrock server, which doesn't use SRV records.
+pub(crate) fn lookup_bedrock(
+    resolver: &Resolver,
+    host: &str,
+    port: u16,
+) -> Result<Resolved, Error> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(Resolved::ip(ip, port));
+    }
+
+    let start = Instant::now();
+    let ip = resolver
+        .lookup_ip(host)
+        .ok()
+        .and_then(|ips| ips.iter().next())
+        .ok_or(Error::DnsLookupFailed)?;
+
+    Ok(Resolved {
+        addr: SocketAddr::new(ip, port),
+        srv_used: false,
+        srv_lookup: None,
+        dns_lookup: Some(start.elapsed()),
+    })
+}
+
+/// Open a TCP connection to `addr`, going through the proxy if one is set.
+pub(crate) fn connect_tcp(
+    net: &Net,
+    addr: SocketAddr,
+    timeout: Option<Duration>,
+) -> Result<TcpStream, Error> {
+    let target = net
+        .proxy
+        .map_or(addr, |Proxy::Socks5 { address, .. }| *address);
+
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(target),
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )?;
+
+    if let Some(ip) = net.bind_address {
+        socket.bind(&SocketAddr::new(ip, 0).into())?;
+    }
+
+    if let Some(timeout) = timeout {
+        socket.connect_timeout(&target.into(), timeout)?;
+    } else {
+        socket.connect(&target.into())?;
+    }
+
+    let mut stream: TcpStream = socket.into();
+    stream.set_nodelay(true)?;
+
+    if let Some(Proxy::Socks5 { credentials, .. }) = net.proxy {
+        // Don't let an unresponsive proxy hang the handshake forever.
+        stream.set_read_timeout(timeout)?;
+        socks5_connect(&mut stream, addr, credentials.as_ref())?;
+        stream.set_read_timeout(None)?;
+    }
+
+    Ok(stream)
+}
+
+/// Perform the SOCKS5 handshake asking the proxy to connect to `addr`.
+///
+/// See https://datatracker.ietf.org/doc/html/rfc1928
+fn socks5_connect<S: Read + Write>(
+    stream: &mut S,
+    addr: SocketAddr,
+    credentials: Option<&(String, String)>,
+) -> Result<(), Error> {
+    stream.write_all(&socks5::greeting(credentials))?;
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+
+    match socks5::method(reply)? {
+        socks5::Method::None => {}
+        socks5::Method::Password => {
+            stream.write_all(&socks5::password(credentials)?)?;
+            stream.read_exact(&mut reply)?;
+            socks5::check_password_reply(reply)?;
+        }
+    }
+
+    stream.write_all(&socks5::connect_request(addr))?;
+
+    let mut header = [0; 4];
+    stream.read_exact(&mut header)?;
+    socks5::check_connect_reply(header)?;
+
+    // Skip the address the proxy bound to.
+    let len = match header[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8()? as usize,
+        _ => return Err(socks5::error("invalid address type")),
+    };
+    let mut bound = vec![0; len];
+    stream.read_exact(&mut bound)?;
+    stream.read_u16::<BigEndian>()?;
+
+    Ok(())
+}
+
+/// The messages of the SOCKS5 protocol, shared by both runtimes.
+pub(crate) mod socks5 {
+    use super::*;
+
+    pub(crate) enum Method {
+        None,
+        Password,
+    }
+
+    pub(crate) fn error(message: &str) -> Error {
+        Error::IoError(io::Error::other(format!("SOCKS5 proxy: {}", message)))
+    }
+
+    pub(crate) fn greeting(credentials: Option<&(String, String)>) -> Vec<u8> {
+        match credentials {
+            Some(_) => vec![0x05, 0x02, 0x00, 0x02],
+            None => vec![0x05, 0x01, 0x00],
+        }
+    }
+
+    pub(crate) fn method(reply: [u8; 2]) -> Result<Method, Error> {
+        match reply {
+            [0x05, 0x00] => Ok(Method::None),
+            [0x05, 0x02] => Ok(Method::Password),
+            _ => Err(error("no acceptable authentication method")),
+        }
+    }
+
+    pub(crate) fn password(credentials: Option<&(String, String)>) -> Result<Vec<u8>, Error> {
+        let (username, password) = credentials.ok_or_else(|| error("credentials are required"))?;
+
+        if username.len() > 255 || password.len() > 255 {
+            return Err(error("credentials are too long"));
+        }
+
+        let mut buf = vec![0x01, username.len() as u8];
+        buf.extend_from_slice(username.as_bytes());
+        buf.push(password.len() as u8);
+        buf.extend_from_slice(password.as_bytes());
+        Ok(buf)
+    }
+
+    pub(crate) fn check_password_reply(reply: [u8; 2]) -> Result<(), Error> {
+        match reply {
+            [0x01, 0x00] => Ok(()),
+            _ => Err(error("authentication failed")),
+        }
+    }
+
+    pub(crate) fn connect_request(addr: SocketAddr) -> Vec<u8> {
+        let mut buf = vec![0x05, 0x01, 0x00];
+
+        match addr.ip() {
+            IpAddr::V4(ip) => {
+                buf.push(0x01);
+                buf.extend_from_slice(&ip.octets());
+            }
+            IpAddr::V6(ip) => {
+                buf.push(0x04);
+                buf.extend_from_slice(&ip.octets());
+            }
+        }
+
+        // Writing to a `Vec` can't fail.
+        buf.write_u16::<BigEndian>(addr.port()).unwrap();
+        buf
+    }
+
+    pub(crate) fn check_connect_reply(header: [u8; 4]) -> Result<(), Error> {
+        match header[..2] {
+            [0x05, 0x00] => Ok(()),
+            [0x05, code] => Err(error(match code {
+                0x02 => "connection not allowed by ruleset",
+                0x03 => "network unreachable",
+                0x04 => "host unreachable",
+                0x05 => "connection refused",
+                0x06 => "TTL expired",
+                _ => "general failure",
+            })),
+            _ => Err(error("invalid reply")),
+        }
+    }
+}
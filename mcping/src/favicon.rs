@@ -0,0 +1,174 @@
+//! Decoding and validating server icons.
+
+use std::fmt;
+
+use crate::{fnv1a, JavaResponse, ServerStatus};
+
+/// The start of every icon a server sends.
+const PREFIX: &str = "data:image/png;base64,";
+/// The first eight bytes of every PNG file.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// A server icon, decoded and checked to be a 64×64 PNG image.
+///
+/// # Examples
+///
+/// ```no_run
+/// let (_, response) = mcping::get_status(mcping::Java {
+///     server_address: "mc.hypixel.net".into(),
+///     ..Default::default()
+/// })?;
+///
+/// if let Some(favicon) = response.decode_favicon()? {
+///     println!("icon {:016x}", favicon.hash());
+///     std::fs::write("icon.png", favicon.as_png())?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Favicon {
+    png: Vec<u8>,
+}
+
+impl Favicon {
+    /// The width and height of server icons, in pixels.
+    pub const SIZE: u32 = 64;
+
+    /// Decode an icon as servers send it, a `data:image/png;base64,` URI.
+    ///
+    /// Line breaks in the Base64 data, which some servers send, are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{Favicon, FaviconError};
+    ///
+    /// // A gray 64×64 icon, and a 16×16 one.
+    /// let icon = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAEAAAABACAAAAACPAi4CAAAAKUlEQVR42u3MQREAAAwCIKMb3RD77SAA6VEEAoFAIBAIBAKBQCAQfA8G320AeXC6efoAAAAASUVORK5CYII=";
+    /// let small = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAABAAAAAQCAAAAAA6mKC9AAAAD0lEQVR42mNoQAMMI1sAAAUMgAHjM1mKAAAAAElFTkSuQmCC";
+    ///
+    /// assert_eq!(Favicon::from_data_uri(icon)?.to_data_uri(), icon);
+    /// assert!(matches!(
+    ///     Favicon::from_data_uri(small),
+    ///     Err(FaviconError::WrongSize { width: 16, height: 16 }),
+    /// ));
+    /// assert!(matches!(
+    ///     Favicon::from_data_uri("data:image/jpeg;base64,AAAA"),
+    ///     Err(FaviconError::MissingPrefix),
+    /// ));
+    /// assert!(matches!(
+    ///     Favicon::from_data_uri("data:image/png;base64,AAAA"),
+    ///     Err(FaviconError::NotPng),
+    /// ));
+    /// # Ok::<(), FaviconError>(())
+    /// ```
+    pub fn from_data_uri(uri: &str) -> Result<Self, FaviconError> {
+        let data = uri
+            .trim()
+            .strip_prefix(PREFIX)
+            .ok_or(FaviconError::MissingPrefix)?;
+        let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+        Self::from_png(base64::decode(data)?)
+    }
+
+    /// Check that `png` is a 64×64 PNG image.
+    pub fn from_png(png: Vec<u8>) -> Result<Self, FaviconError> {
+        // The signature is followed by the IHDR chunk: its length, its type,
+        // then the width and height as big-endian integers.
+        if png.len() < 24 || png[..8] != PNG_SIGNATURE || &png[12..16] != b"IHDR" {
+            return Err(FaviconError::NotPng);
+        }
+
+        let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+        let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+        if width != Self::SIZE || height != Self::SIZE {
+            return Err(FaviconError::WrongSize { width, height });
+        }
+
+        Ok(Self { png })
+    }
+
+    /// The PNG file of the icon.
+    pub fn as_png(&self) -> &[u8] {
+        &self.png
+    }
+
+    pub fn into_png(self) -> Vec<u8> {
+        self.png
+    }
+
+    /// A hash of the icon, to tell when it changes.
+    ///
+    /// It is the same in every version and on every platform, so it can be
+    /// stored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::Favicon;
+    ///
+    /// let icon = Favicon::from_png(base64::decode(
+    ///     "iVBORw0KGgoAAAANSUhEUgAAAEAAAABACAAAAACPAi4CAAAAKUlEQVR42u3MQREAAAwCIKMb3RD77SAA6VEEAoFAIBAIBAKBQCAQfA8G320AeXC6efoAAAAASUVORK5CYII=",
+    /// )?)?;
+    /// assert_eq!(icon.hash(), 0x94d3_b28c_68ed_82e1);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn hash(&self) -> u64 {
+        fnv1a(&self.png)
+    }
+
+    /// The icon as a `data:image/png;base64,` URI, as servers send it.
+    pub fn to_data_uri(&self) -> String {
+        format!("{}{}", PREFIX, base64::encode(&self.png))
+    }
+
+    /// Decode the pixels of the icon.
+    #[cfg(feature = "image")]
+    pub fn to_image(&self) -> Result<image::RgbaImage, FaviconError> {
+        let image = image::load_from_memory_with_format(&self.png, image::ImageFormat::Png)?;
+        Ok(image.to_rgba8())
+    }
+}
+
+impl fmt::Debug for Favicon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Favicon")
+            .field("len", &self.png.len())
+            .field("hash", &format_args!("{:016x}", self.hash()))
+            .finish()
+    }
+}
+
+/// Why a server icon couldn't be decoded.
+#[derive(Debug, thiserror::Error)]
+pub enum FaviconError {
+    #[error("the icon isn't a `data:image/png;base64,` URI")]
+    MissingPrefix,
+    #[error("the icon isn't valid Base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("the icon isn't a PNG image")]
+    NotPng,
+    #[error("the icon is {width}×{height} pixels instead of 64×64")]
+    WrongSize { width: u32, height: u32 },
+    /// The PNG image is corrupt.
+    #[cfg(feature = "image")]
+    #[error("the icon couldn't be decoded: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+impl JavaResponse {
+    /// Decode and check [`JavaResponse::favicon`], if the server sent one.
+    pub fn decode_favicon(&self) -> Result<Option<Favicon>, FaviconError> {
+        self.favicon
+            .as_deref()
+            .map(Favicon::from_data_uri)
+            .transpose()
+    }
+}
+
+impl ServerStatus {
+    /// Decode and check [`ServerStatus::icon`], if the server sent one.
+    pub fn decode_icon(&self) -> Result<Option<Favicon>, FaviconError> {
+        self.icon.as_deref().map(Favicon::from_data_uri).transpose()
+    }
+}
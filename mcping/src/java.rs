@@ -1,19 +1,32 @@
 //! Implementation of the Java Minecraft ping protocol.
 //! https://wiki.vg/Server_List_Ping
 
-use crate::{Error, Pingable};
+use crate::{
+    connect::{self, Net},
+    lenient, AddressPolicy, Error, ErrorKind, LatencySource, ParseWarning, PingMetadata, Pingable,
+    RateLimiter,
+};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::{
     io::{self, Cursor, Read, Write},
-    net::{IpAddr, SocketAddr, TcpStream},
+    net::TcpStream,
     time::{Duration, Instant},
 };
-use thiserror::Error;
-use trust_dns_resolver::{config::*, Resolver};
+
+/// The default port of a Java server.
+pub(crate) const DEFAULT_PORT: u16 = 25565;
+
+/// How long to wait for the pong when no timeout is set.
+pub(crate) const PONG_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Configuration for pinging a Java server.
 ///
+/// If the server closes the connection instead of answering the ping packet,
+/// the round trip of the status request is returned as the latency, which the
+/// [`PingMetadata::latency_source`] of the response records.
+///
 /// # Examples
 ///
 /// ```
@@ -23,9 +36,10 @@ use trust_dns_resolver::{config::*, Resolver};
 /// let bedrock_config = Java {
 ///     server_address: "mc.hypixel.net".to_string(),
 ///     timeout: Some(Duration::from_secs(10)),
+///     ..Default::default()
 /// };
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
 pub struct Java {
     /// The java server address.
     ///
@@ -44,15 +58,67 @@ pub struct Java {
     /// ```
     pub server_address: String,
     /// The connection timeout if a connection cannot be made.
+    ///
+    /// This is also how long to wait for the pong, five seconds if unset.
     pub timeout: Option<Duration>,
+    /// Whether to parse the status response leniently.
+    ///
+    /// When enabled, missing or malformed fields are replaced with defaults
+    /// instead of failing the ping, and every change made is recorded in
+    /// [`JavaResponse::warnings`].
+    pub lenient: bool,
+    /// The addresses the ping may connect to, if restricted.
+    pub address_policy: Option<AddressPolicy>,
+    /// The rate limiter the ping has to get past before connecting.
+    pub rate_limiter: Option<RateLimiter>,
 }
 
 impl Pingable for Java {
     type Response = JavaResponse;
 
-    fn ping(self) -> Result<(u64, Self::Response), crate::Error> {
-        let mut conn = Connection::new(&self.server_address, self.timeout)?;
+    fn ping(self) -> Result<(Duration, Self::Response), crate::Error> {
+        let resolver = connect::resolver()?;
+
+        self.ping_with(&Net {
+            resolver: &resolver,
+            bind_address: None,
+            proxy: None,
+            address_policy: self.address_policy.as_ref(),
+            rate_limiter: self.rate_limiter.as_ref(),
+        })
+    }
+}
+
+impl Java {
+    pub(crate) fn ping_with(&self, net: &Net) -> Result<(Duration, JavaResponse), Error> {
+        let mut conn = Connection::new(net, &self.server_address, self.timeout)?;
+        let mut response = self.status(&mut conn)?;
+
+        // A zero timeout would mean blocking forever.
+        let pong_timeout = self.timeout.unwrap_or(PONG_TIMEOUT);
+        conn.stream
+            .set_read_timeout(Some(pong_timeout.max(Duration::from_millis(1))))?;
+
+        let latency = match conn.ping(rand::random()) {
+            Ok(pong) => {
+                conn.metadata.pong = Some(pong);
+                pong
+            }
+            // Some proxies and server jars close the connection instead of
+            // answering the ping, which shouldn't lose the status.
+            Err(e) if pong_lost(&e) => {
+                conn.metadata.latency_source = LatencySource::Status;
+                conn.metadata.status
+            }
+            Err(e) => return Err(e),
+        };
+
+        response.metadata = Some(conn.metadata);
+        Ok((latency, response))
+    }
 
+    /// Perform the handshake and read the status response.
+    pub(crate) fn status(&self, conn: &mut Connection) -> Result<JavaResponse, Error> {
         // Handshake
         conn.send_packet(Packet::Handshake {
             version: 47,
@@ -62,33 +128,63 @@ impl Pingable for Java {
         })?;
 
         // Request
+        let start = Instant::now();
         conn.send_packet(Packet::Request {})?;
 
-        let resp = match conn.read_packet()? {
-            Packet::Response { response } => serde_json::from_str(&response)?,
+        let response = match conn.read_packet()? {
+            Packet::Response { response } => self.parse_response(response)?,
             _ => return Err(Error::InvalidPacket),
         };
 
-        // Ping Request
-        let r = rand::random();
-        conn.send_packet(Packet::Ping { payload: r })?;
-
-        let before = Instant::now();
-        let ping = match conn.read_packet()? {
-            Packet::Pong { payload } if payload == r => {
-                (Instant::now() - before).as_millis() as u64
-            }
-            _ => return Err(Error::InvalidPacket),
-        };
+        conn.metadata.status = start.elapsed();
+        Ok(response)
+    }
 
-        Ok((ping, resp))
+    pub(crate) fn parse_response(&self, json: String) -> Result<JavaResponse, Error> {
+        if self.lenient {
+            JavaResponse::from_json_lenient(json)
+        } else {
+            JavaResponse::from_json(json)
+        }
     }
 }
 
+/// Whether the pong failed to arrive, rather than arriving wrong, in which
+/// case the status round trip stands in for it.
+pub(crate) fn pong_lost(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::ConnectionReset | ErrorKind::TimedOut
+    )
+}
+
 /// The server status reponse
 ///
 /// More information can be found [here](https://wiki.vg/Server_List_Ping).
-#[derive(Deserialize)]
+///
+/// Serializing a response produces the JSON a server would send, so a
+/// response can be replayed as-is.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::JavaResponse;
+///
+/// let json = r#"{"version":{"name":"1.20.4","protocol":765},
+///     "players":{"max":20,"online":1,"sample":[{"name":"alice","id":"1"}]},
+///     "description":{"text":"A ","extra":[{"text":"server","bold":true}]},
+///     "enforcesSecureChat":true,"forgeData":{"fmlNetworkVersion":3}}"#;
+/// let response = JavaResponse::from_json(json.to_string())?;
+///
+/// let replayed = serde_json::to_string(&response)?;
+/// assert_eq!(
+///     serde_json::from_str::<serde_json::Value>(&replayed)?,
+///     serde_json::from_str::<serde_json::Value>(json)?,
+/// );
+/// assert_eq!(JavaResponse::from_json(replayed)?, response);
+/// # Ok::<(), mcping::Error>(())
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JavaResponse {
     /// The version of the server.
     pub version: Version,
@@ -97,11 +193,92 @@ pub struct JavaResponse {
     /// The description of the server (MOTD).
     pub description: Chat,
     /// The server icon (a Base64-encoded PNG image)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub favicon: Option<String>,
+    /// Whether the server requires clients to sign their chat messages.
+    #[serde(rename = "enforcesSecureChat", skip_serializing_if = "Option::is_none")]
+    pub enforces_secure_chat: Option<bool>,
+    /// Whether the server has chat previews enabled (1.19 - 1.19.2).
+    #[serde(rename = "previewsChat", skip_serializing_if = "Option::is_none")]
+    pub previews_chat: Option<bool>,
+    /// Whether the server prevents chat reports (sent by the No Chat Reports
+    /// mod and plugins like it).
+    #[serde(
+        rename = "preventsChatReports",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub prevents_chat_reports: Option<bool>,
+    /// Every other field in the response.
+    ///
+    /// This includes mod loader information (`modinfo`, `forgeData`) and any
+    /// vendor-specific keys.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+    /// The JSON string exactly as it was received from the server.
+    ///
+    /// This is `None` for responses that weren't created with
+    /// [`JavaResponse::from_json`], is never serialized and is ignored when
+    /// comparing responses.
+    #[serde(skip)]
+    pub raw: Option<String>,
+    /// The fields that had to be changed when parsing the response leniently.
+    ///
+    /// This is never serialized and is ignored when comparing responses.
+    #[serde(skip)]
+    pub warnings: Vec<ParseWarning>,
+    /// How the ping that received this response went.
+    #[serde(skip)]
+    pub metadata: Option<PingMetadata>,
+}
+
+impl JavaResponse {
+    /// Parses a status response, keeping the JSON string in
+    /// [`JavaResponse::raw`].
+    pub fn from_json(json: String) -> Result<Self, Error> {
+        let mut response: Self = serde_json::from_str(&json)?;
+        response.raw = Some(json);
+        Ok(response)
+    }
+
+    /// Parses a status response, replacing missing or malformed fields with
+    /// defaults instead of failing.
+    ///
+    /// Every field that was changed is recorded in [`JavaResponse::warnings`].
+    /// This only fails if `json` isn't a JSON object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::JavaResponse;
+    ///
+    /// let json = r#"{"players":{"max":"20","online":3},"description":"A server"}"#;
+    /// let response = JavaResponse::from_json_lenient(json.to_string())?;
+    ///
+    /// assert_eq!(response.players.max, 20);
+    /// // `players.max` was converted and `version` is missing.
+    /// assert_eq!(response.warnings.len(), 2);
+    /// # Ok::<(), mcping::Error>(())
+    /// ```
+    pub fn from_json_lenient(json: String) -> Result<Self, Error> {
+        lenient::parse(json)
+    }
+}
+
+impl PartialEq for JavaResponse {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.players == other.players
+            && self.description == other.description
+            && self.favicon == other.favicon
+            && self.enforces_secure_chat == other.enforces_secure_chat
+            && self.previews_chat == other.previews_chat
+            && self.prevents_chat_reports == other.prevents_chat_reports
+            && self.extra == other.extra
+    }
 }
 
 /// Information about the server's version
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Version {
     /// The name of the version the server is running
     ///
@@ -112,7 +289,7 @@ pub struct Version {
 }
 
 /// An online player of the server.
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Player {
     /// The name of the player.
     pub name: String,
@@ -121,7 +298,7 @@ pub struct Player {
 }
 
 /// The stats for players on the server.
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Players {
     /// The max amount of players.
     pub max: i64,
@@ -130,22 +307,35 @@ pub struct Players {
     /// A preview of which players are online
     ///
     /// In practice servers often don't send this or use it for more advertising
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sample: Option<Vec<Player>>,
 }
 
-/// This is a partial implemenation of a Minecraft chat component limited to just text
+/// A Minecraft chat component.
+///
+/// Only the text of a component is interpreted, but every other key (colors,
+/// formatting, child components, events, ...) is kept so that the component
+/// serializes back to what the server sent.
 // TODO: Finish this object.
-#[derive(Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Chat {
-    Text { text: String },
+    Text {
+        text: String,
+        /// Child components, which inherit the style of this component.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        extra: Vec<Chat>,
+        /// The remaining keys of the component (`color`, `bold`, ...).
+        #[serde(flatten)]
+        style: Map<String, Value>,
+    },
     String(String),
 }
 
 impl Chat {
     pub fn text(&self) -> &str {
         match self {
-            Chat::Text { text } => text.as_str(),
+            Chat::Text { text, .. } => text.as_str(),
             Chat::String(s) => s.as_str(),
         }
     }
@@ -161,12 +351,12 @@ trait ReadJavaExt: Read + ReadBytesExt {
                 return Ok(res);
             }
         }
-        Err(io::Error::new(io::ErrorKind::Other, "VarInt too big!"))
+        Err(io::Error::other("VarInt too big!"))
     }
 
     fn read_string(&mut self) -> io::Result<String> {
         let len = self.read_varint()? as usize;
-        let mut buf = vec![0; len as usize];
+        let mut buf = vec![0; len];
         self.read_exact(&mut buf)?;
         Ok(String::from_utf8(buf).expect("Invalid UTF-8 String."))
     }
@@ -184,7 +374,7 @@ trait WriteJavaExt: Write + WriteBytesExt {
             self.write_u8((val & 0x7F | 0x80) as u8)?;
             val >>= 7;
         }
-        Err(io::Error::new(io::ErrorKind::Other, "VarInt too big!"))
+        Err(io::Error::other("VarInt too big!"))
     }
 
     fn write_string(&mut self, s: &str) -> io::Result<()> {
@@ -196,12 +386,6 @@ trait WriteJavaExt: Write + WriteBytesExt {
 
 impl<T> WriteJavaExt for T where T: Write + WriteBytesExt {}
 
-#[derive(Debug, Error)]
-#[error("invalid packet response `{packet:?}`")]
-pub struct InvalidPacket {
-    packet: Packet,
-}
-
 #[derive(Debug)]
 pub(crate) enum Packet {
     Handshake {
@@ -222,68 +406,49 @@ pub(crate) enum Packet {
     },
 }
 
-struct Connection {
-    stream: TcpStream,
+pub(crate) struct Connection {
+    pub(crate) stream: TcpStream,
     host: String,
     port: u16,
+    pub(crate) metadata: PingMetadata,
 }
 
 impl Connection {
-    fn new(address: &str, timeout: Option<Duration>) -> Result<Self, Error> {
+    pub(crate) fn new(net: &Net, address: &str, timeout: Option<Duration>) -> Result<Self, Error> {
         // Split the address up into it's parts, saving the host and port for later and converting the
         // potential domain into an ip
-        let mut parts = address.split(':');
+        let (host, port) = connect::parse_address(address, DEFAULT_PORT)?;
+        let resolved = connect::lookup_java(net.resolver, &host, port)?;
 
-        let host = parts.next().ok_or(Error::InvalidAddress)?.to_string();
+        net.check_address(resolved.addr)?;
 
-        // If a port exists we want to try and parse it and if not we will
-        // default to 25565 (Minecraft)
-        let port = if let Some(port) = parts.next() {
-            port.parse::<u16>().map_err(|_| Error::InvalidAddress)?
-        } else {
-            25565
-        };
+        if let Some(limiter) = net.rate_limiter {
+            limiter.wait(&host, resolved.addr.ip())?;
+        }
 
-        // Attempt to lookup the ip of the server from an srv record, falling back on the ip from a host
-        let resolver = Resolver::new(ResolverConfig::default(), ResolverOpts::default()).unwrap();
-
-        // Determine what host to lookup by doing the following:
-        // - Lookup the SRV record for the domain, if it exists perform a lookup of the ip from the target
-        //   and grab the port pointed at by the record.
-        //
-        //   Note: trust_dns_resolver should do a recursive lookup for an ip but it doesn't seem to at
-        //   the moment.
-        //
-        // - If the above failed in any way fall back to the normal ip lookup from the host provided
-        //   and use the provided port.
-        let lookup_ip =
-            |host: &str| -> Option<IpAddr> { resolver.lookup_ip(host).ok()?.into_iter().next() };
-
-        let (ip, port) = resolver
-            .srv_lookup(format!("_minecraft._tcp.{}.", &host))
-            .ok()
-            .and_then(|lookup| {
-                let record = lookup.into_iter().next()?;
-                let ip = lookup_ip(&record.target().to_string())?;
-                Some((ip, record.port()))
-            })
-            .or_else(|| Some((lookup_ip(&host)?, port)))
-            .ok_or(Error::DnsLookupFailed)?;
-
-        let socket_addr = SocketAddr::new(ip, port);
+        let start = Instant::now();
+        let stream = connect::connect_tcp(net, resolved.addr, timeout)?;
 
         Ok(Self {
-            stream: if let Some(timeout) = timeout {
-                TcpStream::connect_timeout(&socket_addr, timeout)?
-            } else {
-                TcpStream::connect(&socket_addr)?
-            },
+            stream,
             host,
-            port,
+            port: resolved.addr.port(),
+            metadata: resolved.metadata(start.elapsed()),
         })
     }
 
-    fn send_packet(&mut self, p: Packet) -> Result<(), Error> {
+    /// Send a ping, returning how long the pong took to arrive.
+    pub(crate) fn ping(&mut self, payload: u64) -> Result<Duration, Error> {
+        let before = Instant::now();
+        self.send_packet(Packet::Ping { payload })?;
+
+        match self.read_packet()? {
+            Packet::Pong { payload: pong } if pong == payload => Ok(before.elapsed()),
+            _ => Err(Error::InvalidPacket),
+        }
+    }
+
+    pub(crate) fn send_packet(&mut self, p: Packet) -> Result<(), Error> {
         let mut buf = Vec::new();
         match p {
             Packet::Handshake {
@@ -307,12 +472,17 @@ impl Connection {
             }
             _ => return Err(Error::InvalidPacket),
         }
-        self.stream.write_varint(buf.len() as i32)?;
-        self.stream.write_all(&buf)?;
+
+        // Write the whole frame at once, so the length and body aren't sent
+        // as separate segments.
+        let mut frame = Vec::with_capacity(buf.len() + 5);
+        frame.write_varint(buf.len() as i32)?;
+        frame.extend_from_slice(&buf);
+        self.stream.write_all(&frame)?;
         Ok(())
     }
 
-    fn read_packet(&mut self) -> Result<Packet, Error> {
+    pub(crate) fn read_packet(&mut self) -> Result<Packet, Error> {
         let len = self.stream.read_varint()?;
         let mut buf = vec![0; len as usize];
         self.stream.read_exact(&mut buf)?;
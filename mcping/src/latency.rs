@@ -0,0 +1,229 @@
+//! Measuring latency with several pings over one Java connection.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    io, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    connect::{self, Net},
+    java::{Connection, Packet},
+    Error, Java, JavaResponse, Pingable, ServerStatus,
+};
+
+/// Configuration for measuring the latency of a Java server more precisely
+/// than a single ping can.
+///
+/// After the status response, `samples` pings are sent over the same
+/// connection, one every `interval`. If a pong doesn't arrive within
+/// `pong_timeout`, the connection may have stopped in the middle of a packet,
+/// so it is closed and that ping and every remaining one are counted as lost.
+///
+/// The latency returned alongside the response is the median of the samples.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{Java, LatencyProbe};
+/// use std::time::Duration;
+///
+/// let (latency, probe) = mcping::get_status(LatencyProbe {
+///     java: Java {
+///         server_address: "mc.hypixel.net".into(),
+///         ..Default::default()
+///     },
+///     samples: 10,
+///     interval: Duration::from_millis(100),
+///     ..Default::default()
+/// })?;
+///
+/// println!("{:?} ± {:?}, {} lost", latency, probe.stats.stddev, probe.stats.lost);
+/// # Ok::<(), mcping::Error>(())
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LatencyProbe {
+    /// The server to measure.
+    pub java: Java,
+    /// How many pings to send. At least one is always sent.
+    pub samples: usize,
+    /// The time between sending two pings.
+    pub interval: Duration,
+    /// How long to wait for each pong.
+    pub pong_timeout: Duration,
+}
+
+impl Default for LatencyProbe {
+    fn default() -> Self {
+        Self {
+            java: Java::default(),
+            samples: 5,
+            interval: Duration::from_millis(200),
+            pong_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Pingable for LatencyProbe {
+    type Response = ProbeResponse;
+
+    fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let resolver = connect::resolver()?;
+
+        self.probe_with(&Net {
+            resolver: &resolver,
+            bind_address: None,
+            proxy: None,
+            address_policy: self.java.address_policy.as_ref(),
+            rate_limiter: self.java.rate_limiter.as_ref(),
+        })
+    }
+}
+
+impl LatencyProbe {
+    /// The number of pings to send.
+    pub(crate) fn samples(&self) -> usize {
+        self.samples.max(1)
+    }
+
+    fn probe_with(&self, net: &Net) -> Result<(Duration, ProbeResponse), Error> {
+        let java = &self.java;
+        let mut conn = Connection::new(net, &java.server_address, java.timeout)?;
+        let mut response = java.status(&mut conn)?;
+
+        // A zero timeout would mean blocking forever.
+        conn.stream
+            .set_read_timeout(Some(self.pong_timeout.max(Duration::from_millis(1))))?;
+
+        let base: u64 = rand::random();
+        let start = Instant::now();
+        let mut samples = Vec::with_capacity(self.samples());
+
+        for i in 0..self.samples() {
+            thread::sleep(
+                offset(self.interval, i)
+                    .and_then(|offset| start.checked_add(offset))
+                    .map_or(self.interval, |at| {
+                        at.saturating_duration_since(Instant::now())
+                    }),
+            );
+
+            match sample(&mut conn, base.wrapping_add(i as u64)) {
+                Ok(rtt) => samples.push(rtt),
+                // The connection is unusable, even after a timeout, so every
+                // remaining ping is lost.
+                Err(_) => break,
+            }
+        }
+
+        let stats = LatencyStats::new(samples, self.samples()).ok_or_else(no_pong)?;
+
+        conn.metadata.pong = Some(stats.median);
+        response.metadata = Some(conn.metadata);
+        Ok((stats.median, ProbeResponse { response, stats }))
+    }
+}
+
+/// Send a ping and wait for its pong, ignoring pongs to other payloads.
+fn sample(conn: &mut Connection, payload: u64) -> Result<Duration, Error> {
+    let before = Instant::now();
+    conn.send_packet(Packet::Ping { payload })?;
+
+    loop {
+        match conn.read_packet()? {
+            Packet::Pong { payload: pong } if pong == payload => return Ok(before.elapsed()),
+            Packet::Pong { .. } => {}
+            _ => return Err(Error::InvalidPacket),
+        }
+    }
+}
+
+/// How long after the first ping the `i`th one is sent, or `None` if that is
+/// further away than a `Duration` can hold.
+pub(crate) fn offset(interval: Duration, i: usize) -> Option<Duration> {
+    interval.checked_mul(u32::try_from(i).ok()?)
+}
+
+pub(crate) fn no_pong() -> Error {
+    io::Error::new(io::ErrorKind::TimedOut, "no pong was received").into()
+}
+
+/// The response of a [`LatencyProbe`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeResponse {
+    /// The status response of the server.
+    pub response: JavaResponse,
+    /// The latency measured with the pings.
+    pub stats: LatencyStats,
+}
+
+impl From<(Duration, ProbeResponse)> for ServerStatus {
+    fn from((latency, probe): (Duration, ProbeResponse)) -> Self {
+        ServerStatus::from((latency, probe.response))
+    }
+}
+
+/// Statistics over the round-trip times of several pings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// The number of pings sent.
+    pub sent: usize,
+    /// The number of pings that weren't answered.
+    pub lost: usize,
+    /// The round-trip time of every answered ping, in the order they were sent.
+    pub samples: Vec<Duration>,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    /// The population standard deviation of the samples.
+    pub stddev: Duration,
+    /// The mean difference between consecutive samples.
+    pub jitter: Duration,
+}
+
+impl LatencyStats {
+    /// Compute the statistics of `samples` out of `sent` pings, if any pong
+    /// was received.
+    pub(crate) fn new(samples: Vec<Duration>, sent: usize) -> Option<Self> {
+        let n = samples.len();
+
+        let mut sorted = samples.clone();
+        sorted.sort();
+
+        let min = *sorted.first()?;
+        let max = *sorted.last()?;
+        // For an odd number of samples both indices are the middle one.
+        let median = (sorted[(n - 1) / 2] + sorted[n / 2]) / 2;
+
+        let mean = samples.iter().sum::<Duration>() / n as u32;
+        let variance = samples
+            .iter()
+            .map(|sample| (sample.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / n as f64;
+
+        let jitter = if n > 1 {
+            samples
+                .windows(2)
+                .map(|pair| pair[1].abs_diff(pair[0]))
+                .sum::<Duration>()
+                / (n - 1) as u32
+        } else {
+            Duration::ZERO
+        };
+
+        Some(Self {
+            sent,
+            lost: sent.saturating_sub(n),
+            samples,
+            min,
+            max,
+            mean,
+            median,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+            jitter,
+        })
+    }
+}
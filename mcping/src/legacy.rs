@@ -0,0 +1,273 @@
+//! Legacy `§` formatting codes, as used by Bedrock MOTDs and many Java
+//! descriptions.
+
+use std::fmt::Write;
+
+use crate::{Chat, Color, Span, Style};
+
+/// The character that starts a formatting code.
+const SECTION: char = '§';
+
+/// Split text with legacy formatting codes into pieces of the same style.
+///
+/// Colors may be one of the sixteen named codes, or a hex color written the
+/// way BungeeCord does, as `§x` followed by six `§`-prefixed hex digits. A
+/// color resets the formatting, as it does in the game.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::{parse_legacy, Color};
+///
+/// let spans = parse_legacy("§x§f§f§8§8§0§0Orange §lbold");
+///
+/// assert_eq!(spans.len(), 2);
+/// assert_eq!(spans[0].text, "Orange ");
+/// assert_eq!(spans[0].style.color, Some(Color::rgb(0xff, 0x88, 0x00)));
+/// assert!(spans[1].style.bold);
+/// ```
+pub fn parse_legacy(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    parse_into(text, Style::default(), &mut spans);
+    spans
+}
+
+/// Rewrite the formatting codes of `text` in a single canonical form.
+///
+/// Codes are lowercased, and hex colors, whether BungeeCord sequences in any
+/// case or the `§#rrggbb` shorthand some plugins use, are written as
+/// BungeeCord sequences, or as the named code of the same color. Text and
+/// unknown codes are left as they are.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::normalize_legacy;
+///
+/// assert_eq!(normalize_legacy("§L§#FF8800hi"), "§l§x§f§f§8§8§0§0hi");
+/// assert_eq!(normalize_legacy("§x§F§F§5§5§5§5red"), "§cred");
+/// ```
+pub fn normalize_legacy(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(index) = rest.find(SECTION) {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some((color, len)) = hex_color(rest) {
+            push_color(&mut out, color);
+            rest = &rest[len..];
+            continue;
+        }
+
+        let mut chars = rest.chars();
+        chars.next();
+        out.push(SECTION);
+        if let Some(code) = chars.next() {
+            out.push(code.to_ascii_lowercase());
+        }
+        rest = chars.as_str();
+    }
+
+    out.push_str(rest);
+    out
+}
+
+impl Chat {
+    /// Convert text with legacy formatting codes into chat components, one
+    /// for each piece of the same style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::Chat;
+    ///
+    /// let motd = Chat::from_legacy("§cRed §lbold");
+    /// assert_eq!(
+    ///     serde_json::to_string(&motd)?,
+    ///     r#"{"text":"","extra":[{"text":"Red ","color":"red"},{"text":"bold","bold":true,"color":"red"}]}"#,
+    /// );
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn from_legacy(text: &str) -> Chat {
+        Chat::Text {
+            text: String::new(),
+            extra: parse_legacy(text)
+                .into_iter()
+                .map(|span| Chat::Text {
+                    text: span.text,
+                    extra: Vec::new(),
+                    style: span.style.keys(),
+                })
+                .collect(),
+            style: Default::default(),
+        }
+    }
+
+    /// The component as text with legacy formatting codes, for places that
+    /// only accept those, such as Bedrock MOTDs or older clients.
+    ///
+    /// Named colors use their code, and other colors are written as
+    /// BungeeCord hex sequences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::Chat;
+    ///
+    /// let motd: Chat = serde_json::from_str(
+    ///     r#"{"text":"A ","color":"gold","extra":[{"text":"server","bold":true}]}"#,
+    /// )?;
+    /// assert_eq!(motd.to_legacy(), "§6A §lserver");
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn to_legacy(&self) -> String {
+        let mut out = String::new();
+        let mut current = Style::default();
+
+        for span in self.spans() {
+            let style = span.style;
+
+            // Formatting can only be turned off by a color or a reset, which
+            // clear all of it.
+            let cleared = (current.bold && !style.bold)
+                || (current.italic && !style.italic)
+                || (current.underlined && !style.underlined)
+                || (current.strikethrough && !style.strikethrough)
+                || (current.obfuscated && !style.obfuscated);
+            if cleared || current.color != style.color {
+                match style.color {
+                    Some(color) => push_color(&mut out, color),
+                    None => {
+                        out.push(SECTION);
+                        out.push('r');
+                    }
+                }
+                current = Style {
+                    color: style.color,
+                    ..Style::default()
+                };
+            }
+
+            for (on, was, code) in [
+                (style.obfuscated, current.obfuscated, 'k'),
+                (style.bold, current.bold, 'l'),
+                (style.strikethrough, current.strikethrough, 'm'),
+                (style.underlined, current.underlined, 'n'),
+                (style.italic, current.italic, 'o'),
+            ] {
+                if on && !was {
+                    out.push(SECTION);
+                    out.push(code);
+                }
+            }
+            current = style;
+
+            out.push_str(&span.text);
+        }
+
+        out
+    }
+}
+
+/// Add the spans of text with legacy formatting codes, starting from `base`.
+pub(crate) fn parse_into(text: &str, base: Style, spans: &mut Vec<Span>) {
+    let mut style = base;
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c != SECTION {
+            push(spans, c, style);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        // A color resets the formatting, as it does in the game.
+        if let Some((color, len)) = hex_color(rest) {
+            style = Style {
+                color: Some(color),
+                ..Style::default()
+            };
+            rest = &rest[len..];
+            continue;
+        }
+
+        let mut chars = rest[c.len_utf8()..].chars();
+        match chars.next().map(|code| code.to_ascii_lowercase()) {
+            Some(code @ ('0'..='9' | 'a'..='f')) => {
+                style = Style {
+                    color: Color::from_code(code),
+                    ..Style::default()
+                }
+            }
+            Some('k') => style.obfuscated = true,
+            Some('l') => style.bold = true,
+            Some('m') => style.strikethrough = true,
+            Some('n') => style.underlined = true,
+            Some('o') => style.italic = true,
+            Some('r') => style = base,
+            _ => {}
+        }
+        rest = chars.as_str();
+    }
+}
+
+/// Parse a hex color at the start of `text`, either a BungeeCord sequence or
+/// the `§#rrggbb` shorthand, returning it with the length of its codes.
+fn hex_color(text: &str) -> Option<(Color, usize)> {
+    let mut chars = text.chars();
+    if chars.next()? != SECTION {
+        return None;
+    }
+
+    let mut hex = String::with_capacity(7);
+    hex.push('#');
+    match chars.next()? {
+        'x' | 'X' => {
+            for _ in 0..6 {
+                if chars.next()? != SECTION {
+                    return None;
+                }
+                hex.push(chars.next()?);
+            }
+        }
+        '#' => {
+            for _ in 0..6 {
+                hex.push(chars.next()?);
+            }
+        }
+        _ => return None,
+    }
+
+    let color = Color::parse(&hex)?;
+    Some((color, text.len() - chars.as_str().len()))
+}
+
+/// Write the code of a color, named if it has a name.
+fn push_color(out: &mut String, color: Color) {
+    match color.code() {
+        Some(code) => {
+            out.push(SECTION);
+            out.push(code);
+        }
+        None => {
+            out.push(SECTION);
+            out.push('x');
+            for digit in color.to_string().chars().skip(1) {
+                let _ = write!(out, "{}{}", SECTION, digit);
+            }
+        }
+    }
+}
+
+/// Add a character, extending the last span if it has the same style.
+fn push(spans: &mut Vec<Span>, c: char, style: Style) {
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push(c),
+        _ => spans.push(Span {
+            text: c.to_string(),
+            style,
+        }),
+    }
+}
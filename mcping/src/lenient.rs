@@ -0,0 +1,324 @@
+//! Lenient parsing of Java status responses.
+//!
+//! Some servers send responses that don't match the format described on
+//! https://wiki.vg/Server_List_Ping, for example `players.max` as a string or no
+//! `version` at all. The functions here recover as much of the response as
+//! possible, filling in defaults and recording a [`ParseWarning`] for every
+//! field that had to be changed.
+
+use serde_json::{Map, Value};
+use std::fmt;
+
+use crate::{Chat, Error, JavaResponse, Player, Players, Version};
+
+/// Describes a field that was changed while leniently parsing a response.
+///
+/// `field` is the path of the field in the response, such as `players.max`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ParseWarning {
+    /// The field was missing and a default value was used.
+    Missing { field: String },
+    /// The field had the wrong type and was converted from `value`.
+    Coerced { field: String, value: String },
+    /// The field had the wrong type and couldn't be converted, so `value` was
+    /// discarded and a default used instead.
+    Invalid { field: String, value: String },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::Missing { field } => write!(f, "`{}` is missing", field),
+            ParseWarning::Coerced { field, value } => {
+                write!(f, "`{}` was converted from `{}`", field, value)
+            }
+            ParseWarning::Invalid { field, value } => {
+                write!(f, "`{}` has an invalid value `{}`", field, value)
+            }
+        }
+    }
+}
+
+/// Parses a status response, falling back to defaults for missing or
+/// malformed fields.
+///
+/// This only fails if `json` isn't a JSON object.
+pub(crate) fn parse(json: String) -> Result<JavaResponse, Error> {
+    let mut object: Map<String, Value> = serde_json::from_str(&json)?;
+
+    let mut parser = Parser::default();
+
+    let version = parser.version(object.remove("version"));
+    let players = parser.players(object.remove("players"));
+    let description = match object.remove("description") {
+        Some(value) => parser.chat("description", value),
+        None => {
+            parser.missing("description");
+            Chat::String(String::new())
+        }
+    };
+    let favicon = parser.optional_string("favicon", object.remove("favicon"));
+    let enforces_secure_chat =
+        parser.optional_bool("enforcesSecureChat", object.remove("enforcesSecureChat"));
+    let previews_chat = parser.optional_bool("previewsChat", object.remove("previewsChat"));
+    let prevents_chat_reports =
+        parser.optional_bool("preventsChatReports", object.remove("preventsChatReports"));
+
+    Ok(JavaResponse {
+        version,
+        players,
+        description,
+        favicon,
+        enforces_secure_chat,
+        previews_chat,
+        prevents_chat_reports,
+        extra: object,
+        raw: Some(json),
+        warnings: parser.warnings,
+        metadata: None,
+    })
+}
+
+#[derive(Default)]
+struct Parser {
+    warnings: Vec<ParseWarning>,
+}
+
+impl Parser {
+    fn missing(&mut self, field: &str) {
+        self.warnings.push(ParseWarning::Missing {
+            field: field.to_string(),
+        });
+    }
+
+    fn coerced(&mut self, field: &str, value: &Value) {
+        self.warnings.push(ParseWarning::Coerced {
+            field: field.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    fn invalid(&mut self, field: &str, value: &Value) {
+        self.warnings.push(ParseWarning::Invalid {
+            field: field.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    /// Takes the object out of `value`, recording a warning if it is missing
+    /// or something else.
+    fn object(&mut self, field: &str, value: Option<Value>) -> Option<Map<String, Value>> {
+        match value {
+            Some(Value::Object(object)) => Some(object),
+            Some(value) => {
+                self.invalid(field, &value);
+                None
+            }
+            None => {
+                self.missing(field);
+                None
+            }
+        }
+    }
+
+    fn version(&mut self, value: Option<Value>) -> Version {
+        let mut object = match self.object("version", value) {
+            Some(object) => object,
+            None => {
+                return Version {
+                    name: String::new(),
+                    protocol: 0,
+                }
+            }
+        };
+
+        Version {
+            name: self.string("version.name", object.remove("name")),
+            protocol: self.integer("version.protocol", object.remove("protocol")),
+        }
+    }
+
+    fn players(&mut self, value: Option<Value>) -> Players {
+        let mut object = match self.object("players", value) {
+            Some(object) => object,
+            None => {
+                return Players {
+                    max: 0,
+                    online: 0,
+                    sample: None,
+                }
+            }
+        };
+
+        let sample = match object.remove("sample") {
+            Some(Value::Array(sample)) => Some(
+                sample
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, player)| self.player(i, player))
+                    .collect(),
+            ),
+            Some(Value::Null) | None => None,
+            Some(value) => {
+                self.invalid("players.sample", &value);
+                None
+            }
+        };
+
+        Players {
+            max: self.integer("players.max", object.remove("max")),
+            online: self.integer("players.online", object.remove("online")),
+            sample,
+        }
+    }
+
+    /// Parses a player of the sample, skipping it if it isn't an object.
+    fn player(&mut self, index: usize, value: Value) -> Option<Player> {
+        let field = format!("players.sample[{}]", index);
+
+        let mut object = match value {
+            Value::Object(object) => object,
+            value => {
+                self.invalid(&field, &value);
+                return None;
+            }
+        };
+
+        Some(Player {
+            name: self.string(&format!("{}.name", field), object.remove("name")),
+            id: self.string(&format!("{}.id", field), object.remove("id")),
+        })
+    }
+
+    fn string(&mut self, field: &str, value: Option<Value>) -> String {
+        match value {
+            Some(Value::String(s)) => s,
+            Some(value @ Value::Number(_)) | Some(value @ Value::Bool(_)) => {
+                self.coerced(field, &value);
+                value.to_string()
+            }
+            Some(value) => {
+                self.invalid(field, &value);
+                String::new()
+            }
+            None => {
+                self.missing(field);
+                String::new()
+            }
+        }
+    }
+
+    fn integer(&mut self, field: &str, value: Option<Value>) -> i64 {
+        let value = match value {
+            Some(value) => value,
+            None => {
+                self.missing(field);
+                return 0;
+            }
+        };
+
+        if let Some(n) = value.as_i64() {
+            return n;
+        }
+
+        let coerced = match &value {
+            Value::Number(n) => n.as_f64().map(|n| n as i64),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+
+        match coerced {
+            Some(n) => {
+                self.coerced(field, &value);
+                n
+            }
+            None => {
+                self.invalid(field, &value);
+                0
+            }
+        }
+    }
+
+    fn optional_string(&mut self, field: &str, value: Option<Value>) -> Option<String> {
+        match value {
+            Some(Value::String(s)) => Some(s),
+            Some(Value::Null) | None => None,
+            Some(value) => {
+                self.invalid(field, &value);
+                None
+            }
+        }
+    }
+
+    fn optional_bool(&mut self, field: &str, value: Option<Value>) -> Option<bool> {
+        match value {
+            Some(Value::Bool(b)) => Some(b),
+            Some(Value::Null) | None => None,
+            Some(value) => {
+                let coerced = match &value {
+                    Value::String(s) => s.trim().to_lowercase().parse().ok(),
+                    Value::Number(n) => n.as_i64().map(|n| n != 0),
+                    _ => None,
+                };
+
+                if coerced.is_some() {
+                    self.coerced(field, &value);
+                } else {
+                    self.invalid(field, &value);
+                }
+
+                coerced
+            }
+        }
+    }
+
+    fn chat(&mut self, field: &str, value: Value) -> Chat {
+        match value {
+            Value::String(s) => Chat::String(s),
+            Value::Object(mut object) => {
+                let text = self.string(&format!("{}.text", field), object.remove("text"));
+
+                let extra = match object.remove("extra") {
+                    Some(Value::Array(extra)) => extra
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, child)| self.chat(&format!("{}.extra[{}]", field, i), child))
+                        .collect(),
+                    Some(value) => {
+                        self.invalid(&format!("{}.extra", field), &value);
+                        Vec::new()
+                    }
+                    None => Vec::new(),
+                };
+
+                Chat::Text {
+                    text,
+                    extra,
+                    style: object,
+                }
+            }
+            // An array of components becomes the children of an empty component.
+            Value::Array(components) => {
+                self.coerced(field, &Value::Array(components.clone()));
+
+                Chat::Text {
+                    text: String::new(),
+                    extra: components
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, child)| self.chat(&format!("{}[{}]", field, i), child))
+                        .collect(),
+                    style: Map::new(),
+                }
+            }
+            Value::Null => {
+                self.invalid(field, &Value::Null);
+                Chat::String(String::new())
+            }
+            value => {
+                self.coerced(field, &value);
+                Chat::String(value.to_string())
+            }
+        }
+    }
+}
@@ -6,16 +6,63 @@
 //! handling (such as SRV record lookup). An async implemention on top of the tokio
 //! runtime is also provided.
 //!
-//! The main API surface is [`get_status`].
+//! The main API surface is [`get_status`], and [`get_status_any`] for code that
+//! handles both editions the same way.
 
 #[cfg(feature = "tokio-runtime")]
 pub mod tokio;
 
+mod auto;
+mod batch;
 mod bedrock;
+mod cache;
+mod connect;
+mod favicon;
 mod java;
+mod latency;
+mod legacy;
+mod lenient;
+mod metadata;
+mod motd;
+mod pinger;
+mod policy;
+mod ratelimit;
+#[cfg(feature = "render")]
+mod render;
+#[cfg(feature = "storage")]
+mod report;
+mod retry;
+mod rules;
+mod status;
+#[cfg(feature = "storage")]
+mod storage;
 
-pub use bedrock::{Bedrock, BedrockResponse};
+pub use auto::{Auto, AutoResponse};
+pub use batch::{get_status_batch, BatchIter, BatchOptions, BatchResult, Target};
+pub use bedrock::{Bedrock, BedrockEdition, BedrockResponse};
+pub use cache::{CacheKey, CacheOptions, Cached, ResponseCache};
+pub use connect::Proxy;
+pub use favicon::{Favicon, FaviconError};
 pub use java::{Chat, Java, JavaResponse, Player, Players, Version};
+pub use latency::{LatencyProbe, LatencyStats, ProbeResponse};
+pub use legacy::{normalize_legacy, parse_legacy};
+pub use lenient::ParseWarning;
+pub use metadata::{LatencySource, PingMetadata};
+pub use motd::{Color, Span, Style};
+pub use pinger::{Pinger, PingerOptions};
+pub use policy::{AddressPolicy, BlockReason};
+pub use ratelimit::{Quota, RateLimitOptions, RateLimiter};
+#[cfg(feature = "render")]
+pub use render::ListEntry;
+#[cfg(feature = "storage")]
+pub use report::{Outage, Report};
+pub use retry::{Attempt, Backoff, Jitter, Retried, Retry, RetryPolicy};
+pub use rules::{Alert, AlertSink, AlertState, Condition, LogSink, Rule, RuleEngine, WebhookSink};
+pub use status::{Edition, ServerStatus};
+#[cfg(feature = "storage")]
+pub use storage::{History, Point, Record, Resolution, Retention, Summary};
+
+use std::time::Duration;
 
 /// Errors that can occur when pinging a server.
 #[derive(Debug, thiserror::Error)]
@@ -30,6 +77,220 @@ pub enum Error {
     InvalidAddress,
     #[error("DNS lookup for the host provided failed")]
     DnsLookupFailed,
+    #[error("{}", exhausted(.0))]
+    RetriesExhausted(Vec<Attempt>),
+    #[error("connecting to {addr} is not allowed because {reason}")]
+    AddressBlocked {
+        addr: std::net::SocketAddr,
+        reason: BlockReason,
+    },
+    #[error("pinging {key} now would exceed its rate limit, retry in {retry_after:?}")]
+    RateLimited { key: String, retry_after: Duration },
+    #[error("the server icon is invalid: {0}")]
+    InvalidFavicon(#[from] FaviconError),
+    #[cfg(feature = "storage")]
+    #[error("a storage error occurred: {0}")]
+    Storage(#[from] rusqlite::Error),
+    #[cfg(feature = "render")]
+    #[error("an image error occurred: {0}")]
+    Image(#[from] image::ImageError),
+    /// An error remembered by a [`ResponseCache`], possibly shared with other
+    /// callers.
+    #[error(transparent)]
+    Cached(std::sync::Arc<Error>),
+}
+
+/// The 64-bit FNV-1a hash, which unlike the standard library's hasher is the
+/// same in every version and on every platform.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Escape text for HTML elements and quoted attributes.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     mcping::escape_html(r#"<a href="x">Tom & 'Jerry'</a>"#),
+///     "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;",
+/// );
+/// ```
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format seconds since the Unix epoch as a UTC date and time, to the
+/// minute.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(mcping::format_utc(1_700_000_000), "2023-11-14 22:13 UTC");
+/// ```
+pub fn format_utc(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60
+    )
+}
+
+fn exhausted(attempts: &[Attempt]) -> String {
+    let last = attempts
+        .last()
+        .and_then(|attempt| attempt.error.as_ref())
+        .map_or_else(String::new, ToString::to_string);
+
+    match attempts.len() {
+        1 => last,
+        n => format!("all {} attempts failed, the last with: {}", n, last),
+    }
+}
+
+impl Error {
+    /// What kind of failure the error is, used to decide whether to retry.
+    ///
+    /// For [`Error::RetriesExhausted`] this is the kind of the last error, and
+    /// for [`Error::Cached`] the kind of the cached error.
+    pub fn kind(&self) -> ErrorKind {
+        use std::io::ErrorKind as Io;
+
+        match self {
+            Error::InvalidPacket | Error::JsonErr(_) => ErrorKind::InvalidResponse,
+            Error::IoError(err) => match err.kind() {
+                // Socket read timeouts surface as `WouldBlock` on some platforms.
+                Io::TimedOut | Io::WouldBlock => ErrorKind::TimedOut,
+                Io::ConnectionRefused => ErrorKind::ConnectionRefused,
+                Io::ConnectionReset
+                | Io::ConnectionAborted
+                | Io::BrokenPipe
+                | Io::UnexpectedEof => ErrorKind::ConnectionReset,
+                Io::InvalidData => ErrorKind::InvalidResponse,
+                _ => ErrorKind::Io,
+            },
+            Error::InvalidAddress => ErrorKind::InvalidAddress,
+            Error::DnsLookupFailed => ErrorKind::DnsLookupFailed,
+            Error::RetriesExhausted(attempts) => attempts
+                .last()
+                .and_then(|attempt| attempt.error.as_ref())
+                .map_or(ErrorKind::Io, Error::kind),
+            Error::AddressBlocked { .. } => ErrorKind::AddressBlocked,
+            Error::RateLimited { .. } => ErrorKind::RateLimited,
+            Error::InvalidFavicon(_) => ErrorKind::InvalidResponse,
+            #[cfg(feature = "storage")]
+            Error::Storage(_) => ErrorKind::Io,
+            #[cfg(feature = "render")]
+            Error::Image(_) => ErrorKind::Io,
+            Error::Cached(err) => err.kind(),
+        }
+    }
+
+    /// A copy of the error to share with other callers, keeping its kind and
+    /// message.
+    ///
+    /// Errors of other crates that can't be cloned become I/O errors.
+    pub(crate) fn duplicate(&self) -> Self {
+        use std::io;
+
+        match self {
+            Error::InvalidPacket => Error::InvalidPacket,
+            Error::IoError(err) => io::Error::new(err.kind(), err.to_string()).into(),
+            Error::JsonErr(err) => Error::JsonErr(serde::de::Error::custom(err)),
+            Error::InvalidAddress => Error::InvalidAddress,
+            Error::DnsLookupFailed => Error::DnsLookupFailed,
+            Error::RetriesExhausted(attempts) => Error::RetriesExhausted(
+                attempts
+                    .iter()
+                    .map(|attempt| Attempt {
+                        started: attempt.started,
+                        duration: attempt.duration,
+                        error: attempt.error.as_ref().map(Error::duplicate),
+                    })
+                    .collect(),
+            ),
+            Error::AddressBlocked { addr, reason } => Error::AddressBlocked {
+                addr: *addr,
+                reason: *reason,
+            },
+            Error::RateLimited { key, retry_after } => Error::RateLimited {
+                key: key.clone(),
+                retry_after: *retry_after,
+            },
+            Error::InvalidFavicon(err) => match err {
+                FaviconError::MissingPrefix => FaviconError::MissingPrefix.into(),
+                FaviconError::Base64(err) => FaviconError::Base64(err.clone()).into(),
+                FaviconError::NotPng => FaviconError::NotPng.into(),
+                FaviconError::WrongSize { width, height } => FaviconError::WrongSize {
+                    width: *width,
+                    height: *height,
+                }
+                .into(),
+                #[cfg(feature = "image")]
+                FaviconError::Image(_) => {
+                    io::Error::new(io::ErrorKind::InvalidData, err.to_string()).into()
+                }
+            },
+            #[cfg(feature = "storage")]
+            Error::Storage(err) => io::Error::other(err.to_string()).into(),
+            #[cfg(feature = "render")]
+            Error::Image(err) => io::Error::other(err.to_string()).into(),
+            Error::Cached(err) => Error::Cached(std::sync::Arc::clone(err)),
+        }
+    }
+}
+
+/// A broad category of [`Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The server address couldn't be parsed.
+    InvalidAddress,
+    /// The host couldn't be resolved.
+    DnsLookupFailed,
+    /// The server didn't answer in time.
+    TimedOut,
+    /// The server refused the connection.
+    ConnectionRefused,
+    /// The connection was closed before the ping finished.
+    ConnectionReset,
+    /// The server answered with something that isn't a valid response.
+    InvalidResponse,
+    /// An [`AddressPolicy`] didn't allow connecting to the server.
+    AddressBlocked,
+    /// A [`RateLimiter`] didn't allow the ping.
+    RateLimited,
+    /// Any other I/O error.
+    Io,
 }
 
 /// Represents a pingable entity.
@@ -38,13 +299,13 @@ pub trait Pingable {
     type Response;
 
     /// Ping the entity, gathering the latency and response.
-    fn ping(self) -> Result<(u64, Self::Response), Error>;
+    fn ping(self) -> Result<(Duration, Self::Response), Error>;
 }
 
 /// Retrieve the status of a given Minecraft server using a `Pingable` configuration.
 ///
 ///
-/// Returns `(latency_ms, response)` where response is a response type of the `Pingable` configuration.
+/// Returns `(latency, response)` where response is a response type of the `Pingable` configuration.
 ///
 /// # Examples
 ///
@@ -56,6 +317,7 @@ pub trait Pingable {
 /// let (latency, response) = mcping::get_status(mcping::Java {
 ///     server_address: "mc.hypixel.net".into(),
 ///     timeout: None,
+///     ..Default::default()
 /// })?;
 /// # Ok::<(), mcping::Error>(())
 /// ```
@@ -73,6 +335,28 @@ pub trait Pingable {
 /// })?;
 /// # Ok::<(), mcping::Error>(())
 /// ```
-pub fn get_status<P: Pingable>(pingable: P) -> Result<(u64, P::Response), Error> {
+pub fn get_status<P: Pingable>(pingable: P) -> Result<(Duration, P::Response), Error> {
     pingable.ping()
 }
+
+/// Retrieve the status of a given Minecraft server as a [`ServerStatus`],
+/// regardless of its edition.
+///
+/// # Examples
+///
+/// ```no_run
+/// let status = mcping::get_status_any(mcping::Java {
+///     server_address: "mc.hypixel.net".into(),
+///     ..Default::default()
+/// })?;
+///
+/// println!("{} ({}): {}", status.version_name, status.edition, status.motd.text());
+/// # Ok::<(), mcping::Error>(())
+/// ```
+pub fn get_status_any<P>(pingable: P) -> Result<ServerStatus, Error>
+where
+    P: Pingable,
+    ServerStatus: From<(Duration, P::Response)>,
+{
+    pingable.ping().map(ServerStatus::from)
+}
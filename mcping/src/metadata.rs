@@ -0,0 +1,51 @@
+//! Details of how a ping went, beyond its latency.
+
+use serde::{Deserialize, Serialize};
+use std::{net::IpAddr, time::Duration};
+
+/// Where a ping was sent and how long each of its phases took.
+///
+/// Responses of pings carry this as their `metadata`. Responses that were
+/// parsed rather than received have none.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PingMetadata {
+    /// The IP address the ping was sent to.
+    ///
+    /// When going through a proxy, this is the address the proxy was asked to
+    /// connect to.
+    pub ip: IpAddr,
+    /// The port the ping was sent to.
+    pub port: u16,
+    /// Whether the address came from a SRV record.
+    pub srv_used: bool,
+    /// How long the SRV lookup took, if one was made.
+    pub srv_lookup: Option<Duration>,
+    /// How long resolving the host took, if it wasn't an IP already.
+    pub dns_lookup: Option<Duration>,
+    /// How long opening the connection took, including any proxy handshake.
+    ///
+    /// For Bedrock, this is how long binding and connecting the UDP socket
+    /// took.
+    pub connect: Duration,
+    /// How long it took from sending the request to receiving the status.
+    ///
+    /// For Bedrock, this includes the waits between resending the ping.
+    pub status: Duration,
+    /// How long the pong took to arrive, for Java pings that were answered.
+    pub pong: Option<Duration>,
+    /// What the latency returned with the response measures.
+    pub latency_source: LatencySource,
+}
+
+/// What the latency of a ping measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LatencySource {
+    /// The round trip of a ping packet.
+    Pong,
+    /// The round trip of the status request, used for Java servers that don't
+    /// answer the ping packet.
+    ///
+    /// This includes the time the server took to build its response, so it is
+    /// usually higher than the pong would have been.
+    Status,
+}
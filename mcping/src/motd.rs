@@ -0,0 +1,474 @@
+//! Rendering chat components, such as MOTDs, as styled text.
+
+use serde_json::{Map, Value};
+use std::fmt::{self, Write};
+
+use crate::{escape_html, legacy, Chat};
+
+/// A color of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// The legacy formatting code, chat component name and value of each of the
+/// sixteen named colors.
+const NAMED: [(char, &str, Color); 16] = [
+    ('0', "black", Color::rgb(0x00, 0x00, 0x00)),
+    ('1', "dark_blue", Color::rgb(0x00, 0x00, 0xaa)),
+    ('2', "dark_green", Color::rgb(0x00, 0xaa, 0x00)),
+    ('3', "dark_aqua", Color::rgb(0x00, 0xaa, 0xaa)),
+    ('4', "dark_red", Color::rgb(0xaa, 0x00, 0x00)),
+    ('5', "dark_purple", Color::rgb(0xaa, 0x00, 0xaa)),
+    ('6', "gold", Color::rgb(0xff, 0xaa, 0x00)),
+    ('7', "gray", Color::rgb(0xaa, 0xaa, 0xaa)),
+    ('8', "dark_gray", Color::rgb(0x55, 0x55, 0x55)),
+    ('9', "blue", Color::rgb(0x55, 0x55, 0xff)),
+    ('a', "green", Color::rgb(0x55, 0xff, 0x55)),
+    ('b', "aqua", Color::rgb(0x55, 0xff, 0xff)),
+    ('c', "red", Color::rgb(0xff, 0x55, 0x55)),
+    ('d', "light_purple", Color::rgb(0xff, 0x55, 0xff)),
+    ('e', "yellow", Color::rgb(0xff, 0xff, 0x55)),
+    ('f', "white", Color::rgb(0xff, 0xff, 0xff)),
+];
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// The color of a legacy formatting code, `0` to `f`.
+    pub fn from_code(code: char) -> Option<Self> {
+        let code = code.to_ascii_lowercase();
+        NAMED
+            .iter()
+            .find(|(c, _, _)| *c == code)
+            .map(|(_, _, color)| *color)
+    }
+
+    /// The color of a chat component, either a name such as `dark_red` or a
+    /// hex color such as `#ff8800`.
+    pub fn parse(color: &str) -> Option<Self> {
+        if let Some((_, _, named)) = NAMED.iter().find(|(_, name, _)| *name == color) {
+            return Some(*named);
+        }
+
+        let hex = color.strip_prefix('#')?;
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+
+        Some(Self::rgb(
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+        ))
+    }
+
+    /// The legacy formatting code of the color, if it is a named color.
+    pub fn code(self) -> Option<char> {
+        NAMED
+            .iter()
+            .find(|(_, _, color)| *color == self)
+            .map(|(code, _, _)| *code)
+    }
+
+    /// The chat component name of the color, if it is a named color.
+    pub fn name(self) -> Option<&'static str> {
+        NAMED
+            .iter()
+            .find(|(_, _, color)| *color == self)
+            .map(|(_, name, _)| *name)
+    }
+}
+
+/// Formats the color as `#rrggbb`.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// How a piece of text is displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Style {
+    /// The color of the text, or `None` for the default color.
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strikethrough: bool,
+    /// Whether the text is replaced with randomly changing characters.
+    pub obfuscated: bool,
+}
+
+impl Style {
+    /// Apply the style keys of a chat component, keeping the other fields.
+    ///
+    /// As in the game, a color that isn't recognized keeps the inherited one.
+    fn apply(&mut self, keys: &Map<String, Value>) {
+        match keys.get("color").and_then(Value::as_str) {
+            Some("reset") => self.color = None,
+            Some(color) => {
+                if let Some(color) = Color::parse(color) {
+                    self.color = Some(color);
+                }
+            }
+            None => {}
+        }
+
+        let flag =
+            |name: &str, current: bool| keys.get(name).and_then(Value::as_bool).unwrap_or(current);
+        self.bold = flag("bold", self.bold);
+        self.italic = flag("italic", self.italic);
+        self.underlined = flag("underlined", self.underlined);
+        self.strikethrough = flag("strikethrough", self.strikethrough);
+        self.obfuscated = flag("obfuscated", self.obfuscated);
+    }
+
+    /// The style keys of a chat component with this style, leaving out those
+    /// that are off.
+    pub(crate) fn keys(&self) -> Map<String, Value> {
+        let mut keys = Map::new();
+        if let Some(color) = self.color {
+            let value = match color.name() {
+                Some(name) => name.to_string(),
+                None => color.to_string(),
+            };
+            keys.insert("color".to_string(), Value::String(value));
+        }
+
+        for (on, name) in [
+            (self.bold, "bold"),
+            (self.italic, "italic"),
+            (self.underlined, "underlined"),
+            (self.strikethrough, "strikethrough"),
+            (self.obfuscated, "obfuscated"),
+        ] {
+            if on {
+                keys.insert(name.to_string(), Value::Bool(true));
+            }
+        }
+        keys
+    }
+}
+
+/// A piece of text with a single style.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// The character shown in place of each obfuscated character, where the
+/// output can't animate it.
+const OBFUSCATED: char = '▒';
+
+impl Chat {
+    /// The text of the component and all of its children, split into pieces
+    /// of the same style.
+    ///
+    /// Both the styles of components and legacy `§` formatting codes in their
+    /// text are applied. Use [`Chat::String`] or [`parse_legacy`] for legacy
+    /// strings such as Bedrock MOTDs.
+    ///
+    /// [`parse_legacy`]: crate::parse_legacy
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{Chat, Color};
+    ///
+    /// let spans = Chat::String("§cRed §lbold".to_string()).spans();
+    ///
+    /// assert_eq!(spans.len(), 2);
+    /// assert_eq!(spans[1].text, "bold");
+    /// assert_eq!(spans[1].style.color, Color::from_code('c'));
+    /// assert!(spans[1].style.bold);
+    /// ```
+    ///
+    /// Children inherit the style of their parent, including its color when
+    /// theirs isn't recognized:
+    ///
+    /// ```
+    /// use mcping::{Chat, Color};
+    ///
+    /// let motd: Chat = serde_json::from_str(
+    ///     r#"{"text":"A ","color":"gold","extra":[{"text":"B","color":"not a color"},{"text":"C","color":"reset"}]}"#,
+    /// )?;
+    /// let spans = motd.spans();
+    ///
+    /// assert_eq!(spans[0].text, "A B");
+    /// assert_eq!(spans[0].style.color, Color::parse("gold"));
+    /// assert_eq!(spans[1].text, "C");
+    /// assert_eq!(spans[1].style.color, None);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn spans(&self) -> Vec<Span> {
+        let mut spans = Vec::new();
+        collect(self, Style::default(), &mut spans);
+        spans
+    }
+
+    /// The text of the component and all of its children, without any
+    /// formatting, including legacy `§` codes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::Chat;
+    ///
+    /// let motd: Chat = serde_json::from_str(
+    ///     r#"{"text":"§aA ","extra":[{"text":"server","bold":true}]}"#,
+    /// )?;
+    /// assert_eq!(motd.plain_text(), "A server");
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn plain_text(&self) -> String {
+        self.spans().into_iter().map(|span| span.text).collect()
+    }
+
+    /// The component as text for terminals, styled with ANSI escape codes
+    /// using 24-bit colors.
+    ///
+    /// Obfuscated characters are replaced with `▒`. Control characters other
+    /// than line breaks are replaced with `�`, so that a server can't send
+    /// escape sequences of its own to the terminal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::Chat;
+    ///
+    /// let motd: Chat = serde_json::from_str(
+    ///     r##"{"text":"","extra":[
+    ///         {"text":"under","underlined":true},
+    ///         {"text":"struck","strikethrough":true},
+    ///         {"text":"secret","obfuscated":true},
+    ///         {"text":"orange","color":"#ff8800"}
+    ///     ]}"##,
+    /// )?;
+    /// assert_eq!(
+    ///     motd.to_ansi(),
+    ///     "\x1b[4munder\x1b[0m\x1b[9mstruck\x1b[0m▒▒▒▒▒▒\x1b[38;2;255;136;0morange\x1b[0m",
+    /// );
+    ///
+    /// let motd = Chat::String("\x1b]0;pwned\x07\x1b[2J\nok".to_string());
+    /// assert_eq!(motd.to_ansi(), "�]0;pwned��[2J\nok");
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        let mut styled = false;
+
+        for span in self.spans() {
+            let mut codes = Vec::new();
+            if let Some(color) = span.style.color {
+                codes.push(format!("38;2;{};{};{}", color.r, color.g, color.b));
+            }
+            for (on, code) in [
+                (span.style.bold, "1"),
+                (span.style.italic, "3"),
+                (span.style.underlined, "4"),
+                (span.style.strikethrough, "9"),
+            ] {
+                if on {
+                    codes.push(code.to_string());
+                }
+            }
+
+            if styled {
+                out.push_str("\x1b[0m");
+            }
+            styled = !codes.is_empty();
+            if styled {
+                let _ = write!(out, "\x1b[{}m", codes.join(";"));
+            }
+
+            let text = span.text.chars().map(defuse_control);
+            if span.style.obfuscated {
+                out.extend(text.map(obfuscate));
+            } else {
+                out.extend(text);
+            }
+        }
+
+        if styled {
+            out.push_str("\x1b[0m");
+        }
+        out
+    }
+
+    /// The component as HTML, with every style inline.
+    ///
+    /// Text is escaped and line breaks become `<br>`, so the output is safe to
+    /// embed in a page. Obfuscated text is blurred and has the class
+    /// `mc-obfuscated`, so that pages can animate it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::Chat;
+    ///
+    /// let html = Chat::String("§c<b>".to_string()).to_html();
+    /// assert_eq!(html, r#"<span style="color:#ff5555">&lt;b&gt;</span>"#);
+    /// ```
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+
+        for span in self.spans() {
+            let mut css = Vec::new();
+            if let Some(color) = span.style.color {
+                css.push(format!("color:{}", color));
+            }
+            if span.style.bold {
+                css.push("font-weight:bold".to_string());
+            }
+            if span.style.italic {
+                css.push("font-style:italic".to_string());
+            }
+            match (span.style.underlined, span.style.strikethrough) {
+                (true, true) => css.push("text-decoration:underline line-through".to_string()),
+                (true, false) => css.push("text-decoration:underline".to_string()),
+                (false, true) => css.push("text-decoration:line-through".to_string()),
+                (false, false) => {}
+            }
+            if span.style.obfuscated {
+                css.push("filter:blur(2px)".to_string());
+            }
+
+            let text = escape_html(&span.text).replace('\n', "<br>");
+
+            if css.is_empty() {
+                out.push_str(&text);
+            } else {
+                let class = if span.style.obfuscated {
+                    " class=\"mc-obfuscated\""
+                } else {
+                    ""
+                };
+                let _ = write!(
+                    out,
+                    "<span{} style=\"{}\">{}</span>",
+                    class,
+                    css.join(";"),
+                    text
+                );
+            }
+        }
+
+        out
+    }
+
+    /// The component as Discord markdown.
+    ///
+    /// Discord can't show colors, so only formatting is kept, with obfuscated
+    /// text hidden behind a spoiler. Markdown in the text is escaped and
+    /// mentions are defused, so the output is safe to send as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::Chat;
+    ///
+    /// let markdown = Chat::String("§lBold §r@everyone".to_string()).to_discord();
+    /// assert_eq!(markdown, "**Bold** @\u{200b}everyone");
+    /// ```
+    pub fn to_discord(&self) -> String {
+        let mut out = String::new();
+
+        for span in self.spans() {
+            let mut markers = Vec::new();
+            if span.style.obfuscated {
+                markers.push("||");
+            }
+            if span.style.strikethrough {
+                markers.push("~~");
+            }
+            if span.style.underlined {
+                markers.push("__");
+            }
+            if span.style.bold {
+                markers.push("**");
+            }
+            if span.style.italic {
+                markers.push("*");
+            }
+
+            let text = escape_markdown(&span.text);
+
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+
+                // Markers only apply when they touch the text, so whitespace
+                // goes outside of them.
+                let trimmed = line.trim();
+                if markers.is_empty() || trimmed.is_empty() {
+                    out.push_str(line);
+                    continue;
+                }
+
+                let start = line.len() - line.trim_start().len();
+                out.push_str(&line[..start]);
+                out.extend(markers.iter().copied());
+                out.push_str(trimmed);
+                out.extend(markers.iter().rev().copied());
+                out.push_str(&line[start + trimmed.len()..]);
+            }
+        }
+
+        out
+    }
+}
+
+/// Add the spans of `chat` and its children, which inherit `style`.
+fn collect(chat: &Chat, mut style: Style, spans: &mut Vec<Span>) {
+    if let Chat::Text { style: keys, .. } = chat {
+        style.apply(keys);
+    }
+
+    legacy::parse_into(chat.text(), style, spans);
+
+    if let Chat::Text { extra, .. } = chat {
+        for child in extra {
+            collect(child, style, spans);
+        }
+    }
+}
+
+fn defuse_control(c: char) -> char {
+    if c.is_control() && c != '\n' {
+        char::REPLACEMENT_CHARACTER
+    } else {
+        c
+    }
+}
+
+fn obfuscate(c: char) -> char {
+    if c.is_whitespace() {
+        c
+    } else {
+        OBFUSCATED
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' | '-' | '[' | ']' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            // A zero-width space keeps `@everyone` and `<@id>` from pinging.
+            '@' => escaped.push_str("@\u{200b}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,238 @@
+//! A long-lived client for pinging many servers.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use trust_dns_resolver::Resolver;
+
+use crate::{
+    auto,
+    connect::{self, Net},
+    retry::retry,
+    AddressPolicy, Attempt, AutoResponse, Bedrock, BedrockResponse, CacheKey, CacheOptions, Error,
+    Java, JavaResponse, Proxy, RateLimiter, ResponseCache, Retried, RetryPolicy,
+};
+
+/// The settings every ping of a [`Pinger`] is made with.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::{CacheOptions, PingerOptions};
+/// use std::time::Duration;
+///
+/// let options = PingerOptions {
+///     timeout: Some(Duration::from_secs(5)),
+///     cache: Some(CacheOptions {
+///         ttl: Duration::from_secs(30),
+///         ..Default::default()
+///     }),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PingerOptions {
+    /// The timeout used for every ping.
+    pub timeout: Option<Duration>,
+    /// How failed pings are retried.
+    pub retry: RetryPolicy,
+    /// How responses are cached, if at all.
+    pub cache: Option<CacheOptions>,
+    /// The addresses pings may connect to, if restricted.
+    ///
+    /// Set this when pinging addresses supplied by untrusted users.
+    pub address_policy: Option<AddressPolicy>,
+    /// The rate limiter every ping has to get past, if any.
+    ///
+    /// Cache hits don't count towards the limit.
+    pub rate_limiter: Option<RateLimiter>,
+    /// The local address to open Java connections from.
+    pub bind_address: Option<IpAddr>,
+    /// The socket addresses to try binding the UDP socket of Bedrock pings to.
+    pub socket_addresses: Vec<SocketAddr>,
+    /// The proxy to open Java connections through.
+    pub proxy: Option<Proxy>,
+    /// Whether Java responses are parsed leniently, see [`Java::lenient`].
+    pub lenient: bool,
+    /// How long [`Pinger::ping_any`] keeps waiting for the other edition after
+    /// one has answered.
+    pub grace_period: Duration,
+}
+
+impl Default for PingerOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            retry: RetryPolicy::default(),
+            cache: None,
+            address_policy: None,
+            rate_limiter: None,
+            bind_address: None,
+            socket_addresses: Bedrock::default().socket_addresses,
+            proxy: None,
+            lenient: false,
+            grace_period: crate::Auto::default().grace_period,
+        }
+    }
+}
+
+impl PingerOptions {
+    pub(crate) fn java(&self, address: &str) -> Java {
+        Java {
+            server_address: address.to_string(),
+            timeout: self.timeout,
+            lenient: self.lenient,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn bedrock(&self, address: &str) -> Bedrock {
+        Bedrock {
+            server_address: address.to_string(),
+            timeout: self.timeout,
+            socket_addresses: self.socket_addresses.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A client that keeps its DNS resolver, settings and cache between pings.
+///
+/// Creating a `Pinger` once and reusing it avoids setting up a resolver for
+/// every ping. Cloning a `Pinger` is cheap, and clones share their resolver and
+/// cache.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{Pinger, PingerOptions};
+/// use std::time::Duration;
+///
+/// let pinger = Pinger::new(PingerOptions {
+///     timeout: Some(Duration::from_secs(5)),
+///     ..Default::default()
+/// })?;
+///
+/// let (latency, response) = pinger.ping_java("mc.hypixel.net")?;
+/// let (latency, response) = pinger.ping_bedrock("play.nethergames.org")?;
+/// # Ok::<(), mcping::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Pinger {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    options: PingerOptions,
+    resolver: Resolver,
+    cache: Option<ResponseCache>,
+}
+
+impl Pinger {
+    /// Create a pinger, setting up its DNS resolver.
+    pub fn new(options: PingerOptions) -> Result<Self, Error> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                resolver: connect::resolver()?,
+                cache: options.cache.map(ResponseCache::new),
+                options,
+            }),
+        })
+    }
+
+    /// The settings of the pinger.
+    pub fn options(&self) -> &PingerOptions {
+        &self.inner.options
+    }
+
+    /// Ping a Java server.
+    pub fn ping_java(&self, address: &str) -> Result<(Duration, JavaResponse), Error> {
+        self.ping_java_retried(address)
+            .map(|(latency, retried)| (latency, retried.response))
+    }
+
+    /// Ping a Java server, returning the outcome of every attempt too.
+    pub fn ping_java_retried(
+        &self,
+        address: &str,
+    ) -> Result<(Duration, Retried<JavaResponse>), Error> {
+        let java = self.inner.options.java(address);
+        let key = java.cache_key()?;
+        let pinger = self.clone();
+
+        self.cached(key, move || {
+            retry(&pinger.inner.options.retry, || {
+                let (java, pinger) = (java.clone(), pinger.clone());
+                move || java.ping_with(&pinger.net())
+            })
+        })
+    }
+
+    /// Ping a Bedrock server.
+    pub fn ping_bedrock(&self, address: &str) -> Result<(Duration, BedrockResponse), Error> {
+        self.ping_bedrock_retried(address)
+            .map(|(latency, retried)| (latency, retried.response))
+    }
+
+    /// Ping a Bedrock server, returning the outcome of every attempt too.
+    pub fn ping_bedrock_retried(
+        &self,
+        address: &str,
+    ) -> Result<(Duration, Retried<BedrockResponse>), Error> {
+        let bedrock = self.inner.options.bedrock(address);
+        let key = bedrock.cache_key()?;
+        let pinger = self.clone();
+
+        self.cached(key, move || {
+            retry(&pinger.inner.options.retry, || {
+                let (bedrock, pinger) = (bedrock.clone(), pinger.clone());
+                move || bedrock.ping_with(&pinger.net())
+            })
+        })
+    }
+
+    /// Ping a server of either edition, like [`Auto`](crate::Auto) does.
+    pub fn ping_any(&self, address: &str) -> Result<(Duration, AutoResponse), Error> {
+        let java = (self.clone(), address.to_string());
+        let bedrock = (self.clone(), address.to_string());
+
+        auto::race(
+            move || java.0.ping_java(&java.1),
+            move || bedrock.0.ping_bedrock(&bedrock.1),
+            self.inner.options.grace_period,
+        )
+    }
+
+    fn cached<R, F>(&self, key: String, ping: F) -> Result<(Duration, Retried<R>), Error>
+    where
+        R: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Result<((Duration, R), Vec<Attempt>), Error> + Send + 'static,
+    {
+        let ((latency, response), attempts) = match &self.inner.cache {
+            Some(cache) => cache.get_or_ping(key, ping)?,
+            None => ping()?,
+        };
+
+        Ok((latency, Retried { response, attempts }))
+    }
+
+    fn net(&self) -> Net<'_> {
+        Net {
+            resolver: &self.inner.resolver,
+            bind_address: self.inner.options.bind_address,
+            proxy: self.inner.options.proxy.as_ref(),
+            address_policy: self.inner.options.address_policy.as_ref(),
+            rate_limiter: self.inner.options.rate_limiter.as_ref(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Pinger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pinger")
+            .field("options", &self.inner.options)
+            .finish()
+    }
+}
@@ -0,0 +1,240 @@
+//! Restricting which addresses pings may connect to.
+
+use ipnet::IpNet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Which addresses pings may connect to, checked once a server's address is
+/// resolved (including through its SRV record) and before connecting.
+///
+/// This keeps addresses supplied by untrusted users from reaching the local
+/// machine or network. Since the address checked is the one connected to,
+/// DNS records changing between lookups can't get around it.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::AddressPolicy;
+///
+/// let policy = AddressPolicy {
+///     blocked_networks: vec!["5.9.0.0/16".parse().unwrap()],
+///     blocked_ports: vec![22, 80, 443],
+///     ..Default::default()
+/// };
+///
+/// assert!(policy.check("127.0.0.1:25565".parse().unwrap()).is_err());
+/// assert!(policy.check("5.9.1.2:25565".parse().unwrap()).is_err());
+/// assert!(policy.check("1.1.1.1:25565".parse().unwrap()).is_ok());
+/// ```
+///
+/// IPv4 addresses embedded in IPv6 ones, whether mapped, behind NAT64 or
+/// 6to4, are checked as the IPv4 address they reach:
+///
+/// ```
+/// use mcping::{AddressPolicy, BlockReason};
+///
+/// let policy = AddressPolicy::default();
+/// let check = |addr: &str| policy.check(addr.parse().unwrap());
+///
+/// assert_eq!(check("[::ffff:10.0.0.1]:25565"), Err(BlockReason::Private));
+/// assert_eq!(check("[64:ff9b::a00:1]:25565"), Err(BlockReason::Private));
+/// assert_eq!(check("[2002:c0a8:101::1]:25565"), Err(BlockReason::Private));
+/// assert_eq!(check("[2002:7f00:1::]:25565"), Err(BlockReason::Loopback));
+/// assert_eq!(check("[2002:101:101::1]:25565"), Ok(()));
+/// ```
+///
+/// Reserved addresses, such as documentation and benchmarking networks, are
+/// blocked too:
+///
+/// ```
+/// use mcping::{AddressPolicy, BlockReason};
+///
+/// let policy = AddressPolicy::default();
+/// let check = |addr: &str| policy.check(addr.parse().unwrap());
+///
+/// for addr in [
+///     "240.1.2.3:25565",
+///     "192.0.0.8:25565",
+///     "198.18.0.1:25565",
+///     "198.19.255.1:25565",
+///     "192.0.2.1:25565",
+///     "198.51.100.1:25565",
+///     "203.0.113.1:25565",
+///     "[2001:db8::1]:25565",
+///     "[64:ff9b::101:101]:25565",
+/// ] {
+///     assert_eq!(check(addr), Err(BlockReason::Reserved), "{}", addr);
+/// }
+///
+/// let policy = AddressPolicy {
+///     block_reserved: false,
+///     ..Default::default()
+/// };
+/// assert!(policy.check("203.0.113.1:25565".parse().unwrap()).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AddressPolicy {
+    /// Block loopback addresses, and the unspecified addresses that connect
+    /// to the local machine as well.
+    pub block_loopback: bool,
+    /// Block private network addresses, including carrier-grade NAT and IPv6
+    /// unique local addresses.
+    pub block_private: bool,
+    /// Block link-local addresses.
+    pub block_link_local: bool,
+    /// Block multicast and broadcast addresses.
+    pub block_multicast: bool,
+    /// Block reserved addresses: the future-use `240.0.0.0/4`, the IETF
+    /// protocol assignments `192.0.0.0/24`, the benchmarking `198.18.0.0/15`,
+    /// the documentation networks of both versions and the NAT64 prefix
+    /// `64:ff9b::/96`.
+    pub block_reserved: bool,
+    /// Further networks to block.
+    pub blocked_networks: Vec<IpNet>,
+    /// Ports to block on every address.
+    pub blocked_ports: Vec<u16>,
+}
+
+impl Default for AddressPolicy {
+    fn default() -> Self {
+        Self {
+            block_loopback: true,
+            block_private: true,
+            block_link_local: true,
+            block_multicast: true,
+            block_reserved: true,
+            blocked_networks: Vec::new(),
+            blocked_ports: Vec::new(),
+        }
+    }
+}
+
+impl AddressPolicy {
+    /// Check whether `addr` may be connected to.
+    pub fn check(&self, addr: SocketAddr) -> Result<(), BlockReason> {
+        // IPv4 addresses can be written as IPv6 ones, which mustn't slip by.
+        let ip = match addr.ip() {
+            IpAddr::V6(ip) => embedded_v4(ip).map_or(IpAddr::V6(ip), IpAddr::V4),
+            ip => ip,
+        };
+
+        let reason = if self.block_loopback && is_loopback(ip) {
+            Some(BlockReason::Loopback)
+        } else if self.block_private && is_private(ip) {
+            Some(BlockReason::Private)
+        } else if self.block_link_local && is_link_local(ip) {
+            Some(BlockReason::LinkLocal)
+        } else if self.block_multicast && is_multicast(ip) {
+            Some(BlockReason::Multicast)
+        } else if self.block_reserved && (is_reserved(ip) || is_reserved(addr.ip())) {
+            Some(BlockReason::Reserved)
+        } else if let Some(network) = self.blocked_networks.iter().find(|net| net.contains(&ip)) {
+            Some(BlockReason::Network(*network))
+        } else if self.blocked_ports.contains(&addr.port()) {
+            Some(BlockReason::Port)
+        } else {
+            None
+        };
+
+        reason.map_or(Ok(()), Err)
+    }
+}
+
+/// Why an [`AddressPolicy`] blocked an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockReason {
+    Loopback,
+    Private,
+    LinkLocal,
+    Multicast,
+    Reserved,
+    /// The address is in one of the policy's blocked networks.
+    Network(IpNet),
+    /// The port is one of the policy's blocked ports.
+    Port,
+}
+
+impl std::fmt::Display for BlockReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockReason::Loopback => f.write_str("it is a loopback address"),
+            BlockReason::Private => f.write_str("it is a private address"),
+            BlockReason::LinkLocal => f.write_str("it is a link-local address"),
+            BlockReason::Multicast => f.write_str("it is a multicast address"),
+            BlockReason::Reserved => f.write_str("it is a reserved address"),
+            BlockReason::Network(network) => write!(f, "it is in the blocked network {}", network),
+            BlockReason::Port => f.write_str("its port is blocked"),
+        }
+    }
+}
+
+/// The IPv4 address an IPv6 one reaches, if it is IPv4-mapped, in the NAT64
+/// prefix `64:ff9b::/96` or a 6to4 address.
+fn embedded_v4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let o = ip.octets();
+
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        Some(ip)
+    } else if in_v6(ip, [0x64, 0xff9b, 0, 0, 0, 0, 0, 0], 96) {
+        Some(Ipv4Addr::new(o[12], o[13], o[14], o[15]))
+    } else if in_v6(ip, [0x2002, 0, 0, 0, 0, 0, 0, 0], 16) {
+        Some(Ipv4Addr::new(o[2], o[3], o[4], o[5]))
+    } else {
+        None
+    }
+}
+
+fn is_loopback(ip: IpAddr) -> bool {
+    match ip {
+        // 0.0.0.0/8 reaches the local machine on most systems.
+        IpAddr::V4(ip) => ip.is_loopback() || ip.octets()[0] == 0,
+        IpAddr::V6(ip) => ip.is_loopback() || ip.is_unspecified(),
+    }
+}
+
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || in_v4(ip, [100, 64, 0, 0], 10),
+        IpAddr::V6(ip) => in_v6(ip, [0xfc00, 0, 0, 0, 0, 0, 0, 0], 7),
+    }
+}
+
+fn is_link_local(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_link_local(),
+        IpAddr::V6(ip) => in_v6(ip, [0xfe80, 0, 0, 0, 0, 0, 0, 0], 10),
+    }
+}
+
+fn is_multicast(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_multicast() || ip.is_broadcast(),
+        IpAddr::V6(ip) => ip.is_multicast(),
+    }
+}
+
+fn is_reserved(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => [
+            ([240, 0, 0, 0], 4),
+            ([192, 0, 0, 0], 24),
+            ([198, 18, 0, 0], 15),
+            ([192, 0, 2, 0], 24),
+            ([198, 51, 100, 0], 24),
+            ([203, 0, 113, 0], 24),
+        ]
+        .iter()
+        .any(|&(network, prefix)| in_v4(ip, network, prefix)),
+        IpAddr::V6(ip) => {
+            in_v6(ip, [0x2001, 0xdb8, 0, 0, 0, 0, 0, 0], 32)
+                || in_v6(ip, [0x64, 0xff9b, 0, 0, 0, 0, 0, 0], 96)
+        }
+    }
+}
+
+fn in_v4(ip: Ipv4Addr, network: [u8; 4], prefix: u32) -> bool {
+    u32::from(ip) >> (32 - prefix) == u32::from(Ipv4Addr::from(network)) >> (32 - prefix)
+}
+
+fn in_v6(ip: Ipv6Addr, network: [u16; 8], prefix: u32) -> bool {
+    u128::from(ip) >> (128 - prefix) == u128::from(Ipv6Addr::from(network)) >> (128 - prefix)
+}
@@ -0,0 +1,329 @@
+//! Limiting how often the same server is pinged.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// How often pings to a single server are allowed, as a token bucket.
+///
+/// Up to `burst` pings can be made at once, after which one more is allowed
+/// every `interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quota {
+    /// How many pings can be made back to back.
+    pub burst: u32,
+    /// How long it takes for one more ping to be allowed.
+    pub interval: Duration,
+}
+
+impl Quota {
+    /// Allow `count` pings a minute, all of which can be made at once.
+    pub fn per_minute(count: u32) -> Self {
+        Self {
+            burst: count,
+            interval: Duration::from_secs(60) / count.max(1),
+        }
+    }
+}
+
+/// How a [`RateLimiter`] limits pings.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::{Quota, RateLimitOptions};
+/// use std::time::Duration;
+///
+/// // Never ping the same address more than 6 times a minute, failing pings
+/// // that would have to wait instead of delaying them.
+/// let options = RateLimitOptions {
+///     per_ip: Some(Quota::per_minute(6)),
+///     max_delay: Some(Duration::ZERO),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RateLimitOptions {
+    /// The limit for each resolved IP address.
+    pub per_ip: Option<Quota>,
+    /// The limit for each hostname, however many addresses it resolves to.
+    ///
+    /// Pings to an IP address only count towards `per_ip`.
+    pub per_host: Option<Quota>,
+    /// How long a ping may be delayed before it fails with
+    /// [`Error::RateLimited`] instead.
+    ///
+    /// `None` delays pings for as long as needed, and `Some(Duration::ZERO)`
+    /// fails every ping over the limit.
+    pub max_delay: Option<Duration>,
+}
+
+impl Default for RateLimitOptions {
+    fn default() -> Self {
+        Self {
+            per_ip: Some(Quota {
+                burst: 3,
+                interval: Duration::from_secs(1),
+            }),
+            per_host: Some(Quota {
+                burst: 3,
+                interval: Duration::from_secs(1),
+            }),
+            max_delay: None,
+        }
+    }
+}
+
+/// Token buckets limiting how often each server is pinged.
+///
+/// The limit is checked once the address of a server is resolved, before
+/// anything is sent to it. Cloning a `RateLimiter` is cheap, and clones share
+/// their buckets, so one limiter can be used from many threads and tasks.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{Java, RateLimitOptions, RateLimiter};
+///
+/// let limiter = RateLimiter::new(RateLimitOptions::default());
+///
+/// for _ in 0..10 {
+///     let (latency, response) = mcping::get_status(Java {
+///         server_address: "mc.hypixel.net".into(),
+///         rate_limiter: Some(limiter.clone()),
+///         ..Default::default()
+///     })?;
+/// }
+/// # Ok::<(), mcping::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct RateLimiter {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    options: RateLimitOptions,
+    state: Mutex<State>,
+}
+
+struct State {
+    buckets: HashMap<Key, Bucket>,
+    /// The number of buckets at which full ones are dropped.
+    sweep_at: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Ip(IpAddr),
+    Host(String),
+}
+
+impl Key {
+    fn quota(&self, options: &RateLimitOptions) -> Option<Quota> {
+        match self {
+            Key::Ip(_) => options.per_ip,
+            Key::Host(_) => options.per_host,
+        }
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Ip(ip) => ip.fmt(f),
+            Key::Host(host) => f.write_str(host),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// The pings currently allowed, negative when pings are waiting.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(quota: Quota, now: Instant) -> Self {
+        Self {
+            tokens: quota.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, quota: Quota, now: Instant) {
+        let refilled = if quota.interval.is_zero() {
+            f64::INFINITY
+        } else {
+            now.duration_since(self.updated).as_secs_f64() / quota.interval.as_secs_f64()
+        };
+
+        self.tokens = (self.tokens + refilled).min(quota.burst as f64);
+        self.updated = now;
+    }
+
+    /// How long a ping taking a token now has to wait.
+    fn wait(&self, quota: Quota) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            quota.interval.mul_f64(1.0 - self.tokens)
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Create a rate limiter with no pings made yet.
+    pub fn new(options: RateLimitOptions) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                options,
+                state: Mutex::new(State {
+                    buckets: HashMap::new(),
+                    sweep_at: 64,
+                }),
+            }),
+        }
+    }
+
+    /// The options the rate limiter was created with.
+    pub fn options(&self) -> &RateLimitOptions {
+        &self.inner.options
+    }
+
+    /// Forget every ping made so far.
+    pub fn clear(&self) {
+        self.inner.state.lock().unwrap().buckets.clear();
+    }
+
+    /// Wait until a ping to `ip`, resolved from `host`, is allowed.
+    pub(crate) fn wait(&self, host: &str, ip: IpAddr) -> Result<(), Error> {
+        thread::sleep(self.reserve(host, ip)?);
+        Ok(())
+    }
+
+    /// Take a token from the bucket of `ip` if one is left, without waiting.
+    ///
+    /// Only `per_ip` applies, so the limiter can limit anything else by
+    /// address too, such as the clients of a server.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{Error, Quota, RateLimitOptions, RateLimiter};
+    /// use std::time::Duration;
+    ///
+    /// let limiter = RateLimiter::new(RateLimitOptions {
+    ///     per_ip: Some(Quota {
+    ///         burst: 2,
+    ///         interval: Duration::from_secs(60),
+    ///     }),
+    ///     ..Default::default()
+    /// });
+    /// let client = "203.0.113.7".parse().unwrap();
+    ///
+    /// assert!(limiter.try_acquire(client).is_ok());
+    /// assert!(limiter.try_acquire(client).is_ok());
+    /// assert!(matches!(
+    ///     limiter.try_acquire(client),
+    ///     Err(Error::RateLimited { retry_after, .. }) if retry_after > Duration::from_secs(59)
+    /// ));
+    /// ```
+    pub fn try_acquire(&self, ip: IpAddr) -> Result<(), Error> {
+        self.take(vec![Key::Ip(ip)], Some(Duration::ZERO)).map(drop)
+    }
+
+    /// Take a token from the buckets of `host` and `ip`, returning how long
+    /// the ping has to wait for it.
+    ///
+    /// Nothing is taken if the wait would be longer than the maximum delay.
+    pub(crate) fn reserve(&self, host: &str, ip: IpAddr) -> Result<Duration, Error> {
+        let mut keys = vec![Key::Ip(ip)];
+        if host.parse::<IpAddr>().is_err() {
+            keys.push(Key::Host(host.to_ascii_lowercase()));
+        }
+
+        self.take(keys, self.inner.options.max_delay)
+    }
+
+    /// Take a token from the bucket of every key, unless one of them would
+    /// wait longer than `max_delay`.
+    fn take(&self, keys: Vec<Key>, max_delay: Option<Duration>) -> Result<Duration, Error> {
+        let options = &self.inner.options;
+        let now = Instant::now();
+        let mut state = self.inner.state.lock().unwrap();
+
+        if state.buckets.len() >= state.sweep_at {
+            state.buckets.retain(|key, bucket| {
+                key.quota(options).is_some_and(|quota| {
+                    bucket.refill(quota, now);
+                    bucket.tokens < quota.burst as f64
+                })
+            });
+            state.sweep_at = (state.buckets.len() * 2).max(64);
+        }
+
+        let mut wait = Duration::ZERO;
+        let mut buckets = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let Some(quota) = key.quota(options) else {
+                continue;
+            };
+
+            let mut bucket = state
+                .buckets
+                .get(&key)
+                .copied()
+                .unwrap_or_else(|| Bucket::full(quota, now));
+            bucket.refill(quota, now);
+
+            let bucket_wait = bucket.wait(quota);
+            if max_delay.is_some_and(|max| bucket_wait > max) {
+                return Err(Error::RateLimited {
+                    key: key.to_string(),
+                    retry_after: bucket_wait,
+                });
+            }
+
+            wait = wait.max(bucket_wait);
+            buckets.push((key, bucket));
+        }
+
+        for (key, mut bucket) in buckets {
+            bucket.tokens -= 1.0;
+            state.buckets.insert(key, bucket);
+        }
+
+        Ok(wait)
+    }
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("options", &self.inner.options)
+            .finish()
+    }
+}
+
+/// Rate limiters are equal when they share their buckets.
+impl PartialEq for RateLimiter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for RateLimiter {}
+
+impl std::hash::Hash for RateLimiter {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.inner).hash(state);
+    }
+}
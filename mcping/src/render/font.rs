@@ -0,0 +1,152 @@
+//! A bitmap font in the style of the Minecraft one, covering printable ASCII.
+
+/// The distance between two lines of text, in pixels.
+pub(super) const LINE_HEIGHT: u32 = 9;
+
+/// A character of the font.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Glyph {
+    /// The width of the glyph, not counting the pixel between characters.
+    pub width: u32,
+    /// The eight rows of the glyph, top to bottom, with the leftmost column in
+    /// the highest bit. Row 6 sits on the baseline and row 7 holds
+    /// descenders.
+    pub rows: [u8; 8],
+}
+
+impl Glyph {
+    /// How far the next character starts, in pixels.
+    pub fn advance(self) -> u32 {
+        self.width + 1
+    }
+
+    /// Whether the pixel at `column` of `row` is set.
+    pub fn is_set(self, column: u32, row: u32) -> bool {
+        self.rows[row as usize] & (0x80 >> column) != 0
+    }
+}
+
+/// The width and rows of the characters from `' '` to `'~'`.
+const GLYPHS: [(u32, [u8; 8]); 95] = [
+    (3, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // ' '
+    (1, [0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x80, 0x00]), // '!'
+    (3, [0xa0, 0xa0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // '"'
+    (5, [0x50, 0x50, 0xf8, 0x50, 0xf8, 0x50, 0x50, 0x00]), // '#'
+    (5, [0x20, 0x78, 0x80, 0x70, 0x08, 0xf0, 0x20, 0x00]), // '$'
+    (5, [0x88, 0x90, 0x10, 0x20, 0x40, 0x48, 0x88, 0x00]), // '%'
+    (5, [0x20, 0x50, 0x20, 0x68, 0x90, 0x90, 0x68, 0x00]), // '&'
+    (1, [0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // '\''
+    (3, [0x20, 0x40, 0x80, 0x80, 0x80, 0x40, 0x20, 0x00]), // '('
+    (3, [0x80, 0x40, 0x20, 0x20, 0x20, 0x40, 0x80, 0x00]), // ')'
+    (4, [0x00, 0x00, 0x90, 0x60, 0x90, 0x00, 0x00, 0x00]), // '*'
+    (5, [0x00, 0x20, 0x20, 0xf8, 0x20, 0x20, 0x00, 0x00]), // '+'
+    (1, [0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x80]), // ','
+    (5, [0x00, 0x00, 0x00, 0xf8, 0x00, 0x00, 0x00, 0x00]), // '-'
+    (1, [0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x00]), // '.'
+    (5, [0x08, 0x10, 0x10, 0x20, 0x40, 0x40, 0x80, 0x00]), // '/'
+    (5, [0x70, 0x88, 0x98, 0xa8, 0xc8, 0x88, 0x70, 0x00]), // '0'
+    (5, [0x20, 0x60, 0x20, 0x20, 0x20, 0x20, 0xf8, 0x00]), // '1'
+    (5, [0x70, 0x88, 0x08, 0x30, 0x40, 0x88, 0xf8, 0x00]), // '2'
+    (5, [0x70, 0x88, 0x08, 0x30, 0x08, 0x88, 0x70, 0x00]), // '3'
+    (5, [0x18, 0x28, 0x48, 0x88, 0xf8, 0x08, 0x08, 0x00]), // '4'
+    (5, [0xf8, 0x80, 0xf0, 0x08, 0x08, 0x88, 0x70, 0x00]), // '5'
+    (5, [0x30, 0x40, 0x80, 0xf0, 0x88, 0x88, 0x70, 0x00]), // '6'
+    (5, [0xf8, 0x88, 0x08, 0x10, 0x20, 0x20, 0x20, 0x00]), // '7'
+    (5, [0x70, 0x88, 0x88, 0x70, 0x88, 0x88, 0x70, 0x00]), // '8'
+    (5, [0x70, 0x88, 0x88, 0x78, 0x08, 0x10, 0x60, 0x00]), // '9'
+    (1, [0x00, 0x80, 0x80, 0x00, 0x00, 0x80, 0x80, 0x00]), // ':'
+    (1, [0x00, 0x80, 0x80, 0x00, 0x00, 0x80, 0x80, 0x80]), // ';'
+    (4, [0x10, 0x20, 0x40, 0x80, 0x40, 0x20, 0x10, 0x00]), // '<'
+    (5, [0x00, 0x00, 0xf8, 0x00, 0x00, 0xf8, 0x00, 0x00]), // '='
+    (4, [0x80, 0x40, 0x20, 0x10, 0x20, 0x40, 0x80, 0x00]), // '>'
+    (5, [0x70, 0x88, 0x08, 0x10, 0x20, 0x00, 0x20, 0x00]), // '?'
+    (6, [0x78, 0x84, 0xb4, 0xb4, 0xbc, 0x80, 0x78, 0x00]), // '@'
+    (5, [0x70, 0x88, 0xf8, 0x88, 0x88, 0x88, 0x88, 0x00]), // 'A'
+    (5, [0xf0, 0x88, 0xf0, 0x88, 0x88, 0x88, 0xf0, 0x00]), // 'B'
+    (5, [0x70, 0x88, 0x80, 0x80, 0x80, 0x88, 0x70, 0x00]), // 'C'
+    (5, [0xf0, 0x88, 0x88, 0x88, 0x88, 0x88, 0xf0, 0x00]), // 'D'
+    (5, [0xf8, 0x80, 0xe0, 0x80, 0x80, 0x80, 0xf8, 0x00]), // 'E'
+    (5, [0xf8, 0x80, 0xe0, 0x80, 0x80, 0x80, 0x80, 0x00]), // 'F'
+    (5, [0x78, 0x80, 0x98, 0x88, 0x88, 0x88, 0x70, 0x00]), // 'G'
+    (5, [0x88, 0x88, 0xf8, 0x88, 0x88, 0x88, 0x88, 0x00]), // 'H'
+    (3, [0xe0, 0x40, 0x40, 0x40, 0x40, 0x40, 0xe0, 0x00]), // 'I'
+    (5, [0x08, 0x08, 0x08, 0x08, 0x08, 0x88, 0x70, 0x00]), // 'J'
+    (5, [0x88, 0x90, 0xe0, 0x90, 0x88, 0x88, 0x88, 0x00]), // 'K'
+    (5, [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xf8, 0x00]), // 'L'
+    (5, [0x88, 0xd8, 0xa8, 0x88, 0x88, 0x88, 0x88, 0x00]), // 'M'
+    (5, [0x88, 0xc8, 0xa8, 0x98, 0x88, 0x88, 0x88, 0x00]), // 'N'
+    (5, [0x70, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70, 0x00]), // 'O'
+    (5, [0xf0, 0x88, 0xf0, 0x80, 0x80, 0x80, 0x80, 0x00]), // 'P'
+    (5, [0x70, 0x88, 0x88, 0x88, 0x88, 0x90, 0x68, 0x00]), // 'Q'
+    (5, [0xf0, 0x88, 0xf0, 0x88, 0x88, 0x88, 0x88, 0x00]), // 'R'
+    (5, [0x78, 0x80, 0x70, 0x08, 0x08, 0x88, 0x70, 0x00]), // 'S'
+    (5, [0xf8, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00]), // 'T'
+    (5, [0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70, 0x00]), // 'U'
+    (5, [0x88, 0x88, 0x88, 0x88, 0x50, 0x50, 0x20, 0x00]), // 'V'
+    (5, [0x88, 0x88, 0x88, 0xa8, 0xa8, 0xd8, 0x88, 0x00]), // 'W'
+    (5, [0x88, 0x50, 0x20, 0x50, 0x88, 0x88, 0x88, 0x00]), // 'X'
+    (5, [0x88, 0x50, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00]), // 'Y'
+    (5, [0xf8, 0x08, 0x10, 0x20, 0x40, 0x80, 0xf8, 0x00]), // 'Z'
+    (3, [0xe0, 0x80, 0x80, 0x80, 0x80, 0x80, 0xe0, 0x00]), // '['
+    (5, [0x80, 0x40, 0x40, 0x20, 0x10, 0x10, 0x08, 0x00]), // '\\'
+    (3, [0xe0, 0x20, 0x20, 0x20, 0x20, 0x20, 0xe0, 0x00]), // ']'
+    (5, [0x20, 0x50, 0x88, 0x00, 0x00, 0x00, 0x00, 0x00]), // '^'
+    (5, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8]), // '_'
+    (2, [0x80, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // '`'
+    (5, [0x00, 0x00, 0x70, 0x08, 0x78, 0x88, 0x78, 0x00]), // 'a'
+    (5, [0x80, 0x80, 0xb0, 0xc8, 0x88, 0x88, 0xf0, 0x00]), // 'b'
+    (5, [0x00, 0x00, 0x70, 0x88, 0x80, 0x88, 0x70, 0x00]), // 'c'
+    (5, [0x08, 0x08, 0x68, 0x98, 0x88, 0x88, 0x78, 0x00]), // 'd'
+    (5, [0x00, 0x00, 0x70, 0x88, 0xf8, 0x80, 0x78, 0x00]), // 'e'
+    (4, [0x30, 0x40, 0xf0, 0x40, 0x40, 0x40, 0x40, 0x00]), // 'f'
+    (5, [0x00, 0x00, 0x78, 0x88, 0x88, 0x78, 0x08, 0xf0]), // 'g'
+    (5, [0x80, 0x80, 0xb0, 0xc8, 0x88, 0x88, 0x88, 0x00]), // 'h'
+    (1, [0x80, 0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]), // 'i'
+    (5, [0x08, 0x00, 0x08, 0x08, 0x08, 0x88, 0x88, 0x70]), // 'j'
+    (4, [0x80, 0x80, 0x90, 0xa0, 0xc0, 0xa0, 0x90, 0x00]), // 'k'
+    (2, [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x40, 0x00]), // 'l'
+    (5, [0x00, 0x00, 0xd0, 0xa8, 0xa8, 0x88, 0x88, 0x00]), // 'm'
+    (5, [0x00, 0x00, 0xf0, 0x88, 0x88, 0x88, 0x88, 0x00]), // 'n'
+    (5, [0x00, 0x00, 0x70, 0x88, 0x88, 0x88, 0x70, 0x00]), // 'o'
+    (5, [0x00, 0x00, 0xb0, 0xc8, 0x88, 0xf0, 0x80, 0x80]), // 'p'
+    (5, [0x00, 0x00, 0x68, 0x98, 0x88, 0x78, 0x08, 0x08]), // 'q'
+    (5, [0x00, 0x00, 0xb0, 0xc8, 0x80, 0x80, 0x80, 0x00]), // 'r'
+    (5, [0x00, 0x00, 0x78, 0x80, 0x70, 0x08, 0xf0, 0x00]), // 's'
+    (3, [0x40, 0x40, 0xe0, 0x40, 0x40, 0x40, 0x20, 0x00]), // 't'
+    (5, [0x00, 0x00, 0x88, 0x88, 0x88, 0x88, 0x78, 0x00]), // 'u'
+    (5, [0x00, 0x00, 0x88, 0x88, 0x88, 0x50, 0x20, 0x00]), // 'v'
+    (5, [0x00, 0x00, 0x88, 0x88, 0xa8, 0xa8, 0x78, 0x00]), // 'w'
+    (5, [0x00, 0x00, 0x88, 0x50, 0x20, 0x50, 0x88, 0x00]), // 'x'
+    (5, [0x00, 0x00, 0x88, 0x88, 0x88, 0x78, 0x08, 0xf0]), // 'y'
+    (5, [0x00, 0x00, 0xf8, 0x10, 0x20, 0x40, 0xf8, 0x00]), // 'z'
+    (4, [0x30, 0x40, 0x40, 0x80, 0x40, 0x40, 0x30, 0x00]), // '{'
+    (1, [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80]), // '|'
+    (4, [0xc0, 0x20, 0x20, 0x10, 0x20, 0x20, 0xc0, 0x00]), // '}'
+    (6, [0x00, 0x00, 0x00, 0x64, 0x98, 0x00, 0x00, 0x00]), // '~'
+];
+
+/// The glyph of a character, `?` for characters the font doesn't have.
+pub(super) fn glyph(c: char) -> Glyph {
+    let index = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    let (width, rows) = GLYPHS[index];
+    Glyph { width, rows }
+}
+
+/// A glyph of the same width as `glyph`, picked from `seed`, to draw
+/// obfuscated text with.
+pub(super) fn scrambled(glyph: Glyph, seed: usize) -> Glyph {
+    let candidates: Vec<_> = GLYPHS
+        .iter()
+        .skip(1)
+        .filter(|(width, _)| *width == glyph.width)
+        .collect();
+    if candidates.is_empty() {
+        return glyph;
+    }
+
+    let (width, rows) = *candidates[seed.wrapping_mul(31) % candidates.len()];
+    Glyph { width, rows }
+}
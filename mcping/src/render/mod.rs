@@ -0,0 +1,461 @@
+//! Drawing a server the way the multiplayer screen lists it.
+
+mod font;
+
+use image::{imageops, png::PngEncoder, ColorType, Rgba, RgbaImage};
+use std::time::Duration;
+
+use self::font::{Glyph, LINE_HEIGHT};
+use crate::{
+    parse_legacy, BedrockResponse, Chat, Color, Error, Favicon, JavaResponse, ServerStatus, Span,
+};
+
+/// The size of an entry, in GUI pixels, as in the game.
+const ENTRY_WIDTH: u32 = 305;
+const ENTRY_HEIGHT: u32 = 32;
+/// The space around the entry, in GUI pixels.
+const MARGIN: u32 = 2;
+/// The size of the server icon, in GUI pixels.
+const ICON_SIZE: u32 = 32;
+/// The size of the ping bars, in GUI pixels.
+const BARS_WIDTH: u32 = 10;
+
+const BACKGROUND: Color = Color::rgb(0x20, 0x20, 0x20);
+const NAME_COLOR: Color = Color::rgb(0xff, 0xff, 0xff);
+const MOTD_COLOR: Color = Color::rgb(0x80, 0x80, 0x80);
+const BAR_COLOR: Color = Color::rgb(0x3e, 0xe2, 0x3e);
+const BAR_SHADOW: Color = Color::rgb(0x1b, 0x63, 0x1b);
+const EMPTY_BAR_COLOR: Color = Color::rgb(0x3a, 0x3a, 0x3a);
+const UNKNOWN_ICON: Color = Color::rgb(0x50, 0x50, 0x50);
+
+/// A server as shown in the multiplayer server list, which can be rendered as
+/// an image.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::ListEntry;
+///
+/// let (latency, response) = mcping::get_status(mcping::Java {
+///     server_address: "mc.hypixel.net".into(),
+///     ..Default::default()
+/// })?;
+///
+/// let mut entry = ListEntry::java(latency, &response);
+/// entry.name = "Hypixel".to_string();
+/// std::fs::write("hypixel.png", entry.to_png(2)?)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ListEntry {
+    /// The name the server was saved as, shown above the MOTD.
+    pub name: String,
+    /// The MOTD, of which the first two lines are shown.
+    pub motd: Chat,
+    /// The server icon, as a `data:image/png;base64,` URI. Rendering an
+    /// invalid one fails with [`Error::InvalidFavicon`].
+    pub icon: Option<String>,
+    /// The latency shown by the ping bars.
+    pub latency: Duration,
+    pub players_online: Option<i64>,
+    pub players_max: Option<i64>,
+}
+
+impl ListEntry {
+    /// The name the game gives servers that are added without one.
+    pub const DEFAULT_NAME: &'static str = "Minecraft Server";
+
+    /// The largest scale an entry is drawn at.
+    pub const MAX_SCALE: u32 = 16;
+
+    /// The entry of a Java server.
+    pub fn java(latency: Duration, response: &JavaResponse) -> Self {
+        Self {
+            name: Self::DEFAULT_NAME.to_string(),
+            motd: response.description.clone(),
+            icon: response.favicon.clone(),
+            latency,
+            players_online: Some(response.players.online),
+            players_max: Some(response.players.max),
+        }
+    }
+
+    /// The entry of a Bedrock server, with the level name as the second line
+    /// of the MOTD.
+    pub fn bedrock(latency: Duration, response: &BedrockResponse) -> Self {
+        let motd = match &response.motd_2 {
+            Some(motd_2) => format!("{}\n{}", response.motd_1, motd_2),
+            None => response.motd_1.clone(),
+        };
+
+        Self {
+            name: Self::DEFAULT_NAME.to_string(),
+            motd: Chat::String(motd),
+            icon: None,
+            latency,
+            players_online: response.players_online,
+            players_max: response.players_max,
+        }
+    }
+
+    /// Draw the entry, with each GUI pixel `scale` pixels wide, as with the
+    /// GUI scale setting of the game. At a scale of 2, the icon is drawn at
+    /// its own size.
+    ///
+    /// The scale is clamped between 1 and [`ListEntry::MAX_SCALE`]. Fails
+    /// with [`Error::InvalidFavicon`] if the icon is invalid; set it to `None`
+    /// to draw the placeholder the game shows instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{Chat, Error, ListEntry};
+    /// use std::time::Duration;
+    ///
+    /// let mut entry = ListEntry {
+    ///     name: "A server".to_string(),
+    ///     motd: Chat::String(String::new()),
+    ///     icon: Some("data:image/png;base64,AAAA".to_string()),
+    ///     latency: Duration::from_millis(40),
+    ///     players_online: None,
+    ///     players_max: None,
+    /// };
+    /// assert!(matches!(entry.render(1), Err(Error::InvalidFavicon(_))));
+    ///
+    /// entry.icon = None;
+    /// assert!(entry.render(1).is_ok());
+    /// ```
+    pub fn render(&self, scale: u32) -> Result<RgbaImage, Error> {
+        let scale = scale.clamp(1, Self::MAX_SCALE);
+        let mut canvas = Canvas::new(ENTRY_WIDTH + 2 * MARGIN, ENTRY_HEIGHT + 2 * MARGIN, scale);
+        let (x, y) = (MARGIN as i32, MARGIN as i32);
+
+        let icon = self
+            .icon
+            .as_deref()
+            .map(|icon| Favicon::from_data_uri(icon)?.to_image())
+            .transpose()?;
+        match icon {
+            Some(icon) => canvas.image(x, y, ICON_SIZE, &icon),
+            None => {
+                canvas.fill(x, y, ICON_SIZE, ICON_SIZE, UNKNOWN_ICON);
+                let question = font::glyph('?');
+                canvas.text(
+                    x + (ICON_SIZE - question.width) as i32 / 2,
+                    y + (ICON_SIZE - LINE_HEIGHT) as i32 / 2,
+                    &[plain("?")],
+                    MOTD_COLOR,
+                );
+            }
+        }
+
+        let text_x = x + ICON_SIZE as i32 + 3;
+        canvas.text(text_x, y + 1, &[plain(&self.name)], NAME_COLOR);
+
+        let motd_width = ENTRY_WIDTH - ICON_SIZE - 2;
+        for (i, line) in wrap(&self.motd.spans(), motd_width)
+            .iter()
+            .take(2)
+            .enumerate()
+        {
+            let line_y = y + 12 + (i as u32 * LINE_HEIGHT) as i32;
+            canvas.text(text_x, line_y, line, MOTD_COLOR);
+        }
+
+        let bars_x = x + (ENTRY_WIDTH - BARS_WIDTH - 5) as i32;
+        canvas.bars(bars_x, y, self.bars());
+
+        let players = self.players();
+        let players_x = bars_x - width(&players) as i32 - 2;
+        canvas.text(players_x, y + 1, &players, MOTD_COLOR);
+
+        Ok(canvas.image)
+    }
+
+    /// Draw the entry as a PNG file. See [`ListEntry::render`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use image::GenericImageView;
+    /// use mcping::{Chat, ListEntry};
+    /// use std::time::Duration;
+    ///
+    /// let entry = ListEntry {
+    ///     name: "A server".to_string(),
+    ///     motd: Chat::String("§aWelcome!".to_string()),
+    ///     icon: None,
+    ///     latency: Duration::from_millis(40),
+    ///     players_online: Some(3),
+    ///     players_max: Some(20),
+    /// };
+    ///
+    /// let png = image::load_from_memory(&entry.to_png(2)?)?;
+    /// assert_eq!((png.width(), png.height()), (618, 72));
+    ///
+    /// let huge = entry.render(u32::MAX)?;
+    /// assert_eq!(huge.width(), 309 * ListEntry::MAX_SCALE);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_png(&self, scale: u32) -> Result<Vec<u8>, Error> {
+        let image = self.render(scale)?;
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png).encode(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            ColorType::Rgba8,
+        )?;
+        Ok(png)
+    }
+
+    /// The number of ping bars lit for the latency, out of five, with the
+    /// game's thresholds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{Chat, ListEntry};
+    /// use std::time::Duration;
+    ///
+    /// let bars = |millis| {
+    ///     ListEntry {
+    ///         name: String::new(),
+    ///         motd: Chat::String(String::new()),
+    ///         icon: None,
+    ///         latency: Duration::from_millis(millis),
+    ///         players_online: None,
+    ///         players_max: None,
+    ///     }
+    ///     .bars()
+    /// };
+    ///
+    /// assert_eq!(bars(0), 5);
+    /// assert_eq!(bars(149), 5);
+    /// assert_eq!(bars(150), 4);
+    /// assert_eq!(bars(299), 4);
+    /// assert_eq!(bars(300), 3);
+    /// assert_eq!(bars(599), 3);
+    /// assert_eq!(bars(600), 2);
+    /// assert_eq!(bars(999), 2);
+    /// assert_eq!(bars(1000), 1);
+    /// ```
+    pub fn bars(&self) -> u32 {
+        match self.latency.as_millis() {
+            0..=149 => 5,
+            150..=299 => 4,
+            300..=599 => 3,
+            600..=999 => 2,
+            _ => 1,
+        }
+    }
+
+    /// The player count, as the game shows it.
+    fn players(&self) -> Vec<Span> {
+        match (self.players_online, self.players_max) {
+            (Some(online), Some(max)) => parse_legacy(&format!("§7{}§8/§7{}", online, max)),
+            (Some(online), None) => parse_legacy(&format!("§7{}", online)),
+            _ => parse_legacy("§8???"),
+        }
+    }
+}
+
+impl From<&ServerStatus> for ListEntry {
+    fn from(status: &ServerStatus) -> Self {
+        Self {
+            name: Self::DEFAULT_NAME.to_string(),
+            motd: status.motd.clone(),
+            icon: status.icon.clone(),
+            latency: status.latency,
+            players_online: status.players_online,
+            players_max: status.players_max,
+        }
+    }
+}
+
+/// An image drawn on in GUI pixels.
+struct Canvas {
+    image: RgbaImage,
+    scale: u32,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, scale: u32) -> Self {
+        let background = Rgba([BACKGROUND.r, BACKGROUND.g, BACKGROUND.b, 0xff]);
+        Self {
+            image: RgbaImage::from_pixel(width * scale, height * scale, background),
+            scale,
+        }
+    }
+
+    /// Fill a rectangle given in GUI pixels.
+    fn fill(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        let scale = self.scale as i32;
+        self.fill_pixels(
+            x * scale,
+            y * scale,
+            width * self.scale,
+            height * self.scale,
+            color,
+        );
+    }
+
+    /// Fill a rectangle given in pixels of the image.
+    fn fill_pixels(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        let pixel = Rgba([color.r, color.g, color.b, 0xff]);
+        for py in y.max(0)..(y + height as i32).min(self.image.height() as i32) {
+            for px in x.max(0)..(x + width as i32).min(self.image.width() as i32) {
+                self.image.put_pixel(px as u32, py as u32, pixel);
+            }
+        }
+    }
+
+    /// Draw an image scaled to a square of `size` GUI pixels.
+    fn image(&mut self, x: i32, y: i32, size: u32, image: &RgbaImage) {
+        let size = size * self.scale;
+        let filter = if image.width() > size {
+            imageops::FilterType::Triangle
+        } else {
+            imageops::FilterType::Nearest
+        };
+        let resized = imageops::resize(image, size, size, filter);
+        let scale = self.scale as i32;
+        imageops::overlay(
+            &mut self.image,
+            &resized,
+            (x * scale) as u32,
+            (y * scale) as u32,
+        );
+    }
+
+    /// Draw styled text with a shadow, as the game does.
+    fn text(&mut self, x: i32, y: i32, spans: &[Span], default: Color) {
+        let mut x = x;
+        let mut seed = 0;
+
+        for span in spans {
+            let color = span.style.color.unwrap_or(default);
+            for c in span.text.chars() {
+                let mut glyph = font::glyph(c);
+                if span.style.obfuscated && c != ' ' {
+                    seed += 1;
+                    glyph = font::scrambled(glyph, seed);
+                }
+
+                for (dx, color) in [(1, shadow(color)), (0, color)] {
+                    self.glyph(x + dx, y + dx, glyph, span, color);
+                }
+                x += advance(glyph, span) as i32;
+            }
+        }
+    }
+
+    /// Draw a glyph with the formatting of `span`.
+    fn glyph(&mut self, x: i32, y: i32, glyph: Glyph, span: &Span, color: Color) {
+        let scale = self.scale as i32;
+        let advance = advance(glyph, span);
+
+        for row in 0..8 {
+            // Italic text leans to the right by a pixel every four rows.
+            let lean = if span.style.italic {
+                (6 - row as i32) * scale / 4
+            } else {
+                0
+            };
+
+            for column in 0..glyph.width {
+                if !glyph.is_set(column, row) {
+                    continue;
+                }
+                let px = (x + column as i32) * scale + lean;
+                let py = (y + row as i32) * scale;
+                self.fill_pixels(px, py, self.scale, self.scale, color);
+                if span.style.bold {
+                    self.fill_pixels(px + scale, py, self.scale, self.scale, color);
+                }
+            }
+        }
+
+        if span.style.strikethrough {
+            self.fill(x - 1, y + 3, advance + 1, 1, color);
+        }
+        if span.style.underlined {
+            self.fill(x - 1, y + 8, advance + 1, 1, color);
+        }
+    }
+
+    /// Draw the ping bars with `count` of five lit.
+    fn bars(&mut self, x: i32, y: i32, count: u32) {
+        for bar in 0..5 {
+            let height = bar + 2;
+            let bar_x = x + 2 * bar as i32;
+            let bar_y = y + 8 - height as i32;
+            if bar < count {
+                self.fill(bar_x, bar_y, 1, height, BAR_COLOR);
+                self.fill(bar_x + 1, bar_y + 1, 1, height - 1, BAR_SHADOW);
+            } else {
+                self.fill(bar_x, bar_y, 1, height, EMPTY_BAR_COLOR);
+            }
+        }
+    }
+}
+
+/// The color of the shadow of text of the given color.
+fn shadow(color: Color) -> Color {
+    Color::rgb(color.r / 4, color.g / 4, color.b / 4)
+}
+
+fn advance(glyph: Glyph, span: &Span) -> u32 {
+    glyph.advance() + span.style.bold as u32
+}
+
+/// The width of styled text, in GUI pixels.
+fn width(spans: &[Span]) -> u32 {
+    spans
+        .iter()
+        .flat_map(|span| {
+            span.text
+                .chars()
+                .map(move |c| advance(font::glyph(c), span))
+        })
+        .sum()
+}
+
+fn plain(text: &str) -> Span {
+    Span {
+        text: text.to_string(),
+        style: Default::default(),
+    }
+}
+
+/// Split styled text into lines at line breaks, and wherever a line would be
+/// wider than `max_width`.
+fn wrap(spans: &[Span], max_width: u32) -> Vec<Vec<Span>> {
+    let mut lines: Vec<Vec<Span>> = vec![Vec::new()];
+    let mut line_width = 0;
+
+    for span in spans {
+        for c in span.text.chars() {
+            let advance = advance(font::glyph(c), span);
+            if c == '\n' || line_width + advance > max_width {
+                lines.push(Vec::new());
+                line_width = 0;
+                if c == '\n' {
+                    continue;
+                }
+            }
+
+            let line = lines.last_mut().expect("there is always a line");
+            match line.last_mut() {
+                Some(last) if last.style == span.style => last.text.push(c),
+                _ => line.push(Span {
+                    text: c.to_string(),
+                    style: span.style,
+                }),
+            }
+            line_width += advance;
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,417 @@
+//! Uptime reports over the history of a server.
+
+use std::{fmt::Write, time::Duration};
+
+use crate::{escape_html, format_utc, Error, History, Point, Resolution};
+
+/// Availability, outages, latency and players of a server over a range of
+/// time, computed from its [`History`].
+///
+/// Each point of the history is taken to describe the server until the next
+/// one. At a coarser [`Resolution`] than `Raw`, a bucket counts as an outage
+/// only if every ping in it failed, and latency percentiles are taken over the
+/// mean latency of each bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub target: String,
+    /// The start of the range, in seconds since the Unix epoch.
+    pub from: u64,
+    /// The end of the range, in seconds since the Unix epoch.
+    pub to: u64,
+    /// The number of pings made.
+    pub samples: u64,
+    /// The fraction of the time the server was up, if it was pinged at all.
+    pub availability: Option<f64>,
+    /// Every outage in the range, oldest first.
+    pub outages: Vec<Outage>,
+    /// The mean time to recovery of the outages that ended.
+    pub mttr: Option<Duration>,
+    pub latency_p50: Option<Duration>,
+    pub latency_p95: Option<Duration>,
+    pub latency_p99: Option<Duration>,
+    /// The most players online at once.
+    pub peak_players: Option<i64>,
+    /// When the most players were online, in seconds since the Unix epoch.
+    pub peak_players_at: Option<u64>,
+}
+
+/// A span of time a server was down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Outage {
+    /// When the first failed ping was made, in seconds since the Unix epoch.
+    pub start: u64,
+    /// When the server answered again, or `None` if it was still down at the
+    /// end of the range.
+    pub end: Option<u64>,
+    /// How long the server was down, up to its last failed ping if it never
+    /// came back.
+    pub duration: Duration,
+}
+
+impl Report {
+    /// Compute the report of `target` from its points between `from` and
+    /// `to`, oldest first.
+    pub fn new(target: &str, from: u64, to: u64, points: &[Point]) -> Self {
+        let mut outages = Vec::new();
+        let mut down_since = None;
+
+        for point in points {
+            match (point.up == 0, down_since) {
+                (true, None) => down_since = Some(point.timestamp),
+                (false, Some(start)) => {
+                    outages.push(Outage {
+                        start,
+                        end: Some(point.timestamp),
+                        duration: Duration::from_secs(point.timestamp - start),
+                    });
+                    down_since = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let (Some(start), Some(last)) = (down_since, points.last()) {
+            outages.push(Outage {
+                start,
+                end: None,
+                duration: Duration::from_secs(last.timestamp - start),
+            });
+        }
+
+        let resolved: Vec<_> = outages
+            .iter()
+            .filter(|outage| outage.end.is_some())
+            .collect();
+        let mttr = if resolved.is_empty() {
+            None
+        } else {
+            Some(
+                resolved
+                    .iter()
+                    .map(|outage| outage.duration)
+                    .sum::<Duration>()
+                    / resolved.len() as u32,
+            )
+        };
+
+        let mut latencies: Vec<_> = points
+            .iter()
+            .filter_map(|point| point.latency_mean)
+            .collect();
+        latencies.sort();
+
+        let peak = points
+            .iter()
+            .filter_map(|point| Some((point.players_online_max?, point.timestamp)))
+            .max_by_key(|&(players, timestamp)| (players, std::cmp::Reverse(timestamp)));
+
+        Self {
+            target: target.to_string(),
+            from,
+            to,
+            samples: points.iter().map(|point| point.samples).sum(),
+            availability: availability(points),
+            outages,
+            mttr,
+            latency_p50: percentile(&latencies, 50),
+            latency_p95: percentile(&latencies, 95),
+            latency_p99: percentile(&latencies, 99),
+            peak_players: peak.map(|(players, _)| players),
+            peak_players_at: peak.map(|(_, timestamp)| timestamp),
+        }
+    }
+
+    /// The longest outage, the earliest one if several are as long.
+    pub fn longest_outage(&self) -> Option<&Outage> {
+        self.outages
+            .iter()
+            .rev()
+            .max_by_key(|outage| outage.duration)
+    }
+
+    /// The total time the server was down.
+    pub fn downtime(&self) -> Duration {
+        self.outages.iter().map(|outage| outage.duration).sum()
+    }
+
+    /// Render the report as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# Uptime report for {}", self.target);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{} to {}", format_utc(self.from), format_utc(self.to));
+        let _ = writeln!(out);
+        let _ = writeln!(out, "| | |");
+        let _ = writeln!(out, "|---|---|");
+        for (name, value) in self.rows() {
+            let _ = writeln!(out, "| {} | {} |", name, value);
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "## Outages");
+        let _ = writeln!(out);
+        if self.outages.is_empty() {
+            let _ = writeln!(out, "None.");
+        } else {
+            let _ = writeln!(out, "| Start | End | Duration |");
+            let _ = writeln!(out, "|---|---|---|");
+            for outage in &self.outages {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    format_utc(outage.start),
+                    outage.end.map_or_else(|| "ongoing".to_string(), format_utc),
+                    format_duration(outage.duration)
+                );
+            }
+        }
+
+        out
+    }
+
+    /// Render the report as a JSON object, with durations in seconds and
+    /// times in seconds since the Unix epoch.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let seconds = |duration: Option<Duration>| duration.map(|d| d.as_secs_f64());
+
+        let outages: Vec<_> = self
+            .outages
+            .iter()
+            .map(|outage| {
+                serde_json::json!({
+                    "start": outage.start,
+                    "end": outage.end,
+                    "duration": outage.duration.as_secs_f64(),
+                })
+            })
+            .collect();
+
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "target": self.target,
+            "from": self.from,
+            "to": self.to,
+            "samples": self.samples,
+            "availability": self.availability,
+            "downtime": self.downtime().as_secs_f64(),
+            "mttr": seconds(self.mttr),
+            "longest_outage": seconds(self.longest_outage().map(|outage| outage.duration)),
+            "latency": {
+                "p50": seconds(self.latency_p50),
+                "p95": seconds(self.latency_p95),
+                "p99": seconds(self.latency_p99),
+            },
+            "peak_players": self.peak_players,
+            "peak_players_at": self.peak_players_at,
+            "outages": outages,
+        }))?)
+    }
+
+    /// Render the report as a standalone HTML page.
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        let title = format!("Uptime report for {}", escape_html(&self.target));
+
+        let _ = writeln!(out, "<!DOCTYPE html>");
+        let _ = writeln!(out, "<html lang=\"en\">");
+        let _ = writeln!(out, "<head>");
+        let _ = writeln!(out, "<meta charset=\"utf-8\">");
+        let _ = writeln!(out, "<title>{}</title>", title);
+        let _ = writeln!(
+            out,
+            "<style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+             td,th{{border:1px solid #ccc;padding:.3em .8em;text-align:left}}</style>"
+        );
+        let _ = writeln!(out, "</head>");
+        let _ = writeln!(out, "<body>");
+        let _ = writeln!(out, "<h1>{}</h1>", title);
+        let _ = writeln!(
+            out,
+            "<p>{} to {}</p>",
+            format_utc(self.from),
+            format_utc(self.to)
+        );
+
+        let _ = writeln!(out, "<table>");
+        for (name, value) in self.rows() {
+            let _ = writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", name, value);
+        }
+        let _ = writeln!(out, "</table>");
+
+        let _ = writeln!(out, "<h2>Outages</h2>");
+        if self.outages.is_empty() {
+            let _ = writeln!(out, "<p>None.</p>");
+        } else {
+            let _ = writeln!(out, "<table>");
+            let _ = writeln!(out, "<tr><th>Start</th><th>End</th><th>Duration</th></tr>");
+            for outage in &self.outages {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    format_utc(outage.start),
+                    outage.end.map_or_else(|| "ongoing".to_string(), format_utc),
+                    format_duration(outage.duration)
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+
+        let _ = writeln!(out, "</body>");
+        let _ = writeln!(out, "</html>");
+
+        out
+    }
+
+    /// The summary shared by the Markdown and HTML reports.
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let none = || "n/a".to_string();
+        let latency = |latency: Option<Duration>| {
+            latency.map_or_else(none, |d| format!("{:.1} ms", d.as_secs_f64() * 1000.0))
+        };
+
+        vec![
+            (
+                "Availability",
+                self.availability
+                    .map_or_else(none, |a| format!("{:.3}%", a * 100.0)),
+            ),
+            ("Pings", self.samples.to_string()),
+            ("Outages", self.outages.len().to_string()),
+            ("Downtime", format_duration(self.downtime())),
+            ("MTTR", self.mttr.map_or_else(none, format_duration)),
+            (
+                "Longest outage",
+                self.longest_outage().map_or_else(none, |outage| {
+                    format!(
+                        "{} from {}",
+                        format_duration(outage.duration),
+                        format_utc(outage.start)
+                    )
+                }),
+            ),
+            ("Latency p50", latency(self.latency_p50)),
+            ("Latency p95", latency(self.latency_p95)),
+            ("Latency p99", latency(self.latency_p99)),
+            (
+                "Peak players",
+                match (self.peak_players, self.peak_players_at) {
+                    (Some(players), Some(at)) => format!("{} at {}", players, format_utc(at)),
+                    _ => none(),
+                },
+            ),
+        ]
+    }
+}
+
+impl History {
+    /// The report of `target` from `from` up to `to`, computed from its
+    /// history at the given resolution.
+    ///
+    /// Raw records give exact results, but are only kept for as long as the
+    /// [`Retention`](crate::Retention) allows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{History, Outage, Record, Resolution, Retention};
+    /// use std::time::Duration;
+    ///
+    /// let history = History::in_memory(Retention::default())?;
+    ///
+    /// // A ping a minute, failing for two minutes in the middle.
+    /// let start = 1_700_000_000;
+    /// let pings = [(10, 5), (20, 8), (0, 0), (0, 0), (30, 8), (40, 3)];
+    /// for (i, &(latency, players)) in pings.iter().enumerate() {
+    ///     let up = latency > 0;
+    ///     history.record(&Record {
+    ///         timestamp: start + 60 * i as u64,
+    ///         target: "example.com".to_string(),
+    ///         up,
+    ///         latency: Some(Duration::from_millis(latency)).filter(|_| up),
+    ///         players_online: Some(players).filter(|_| up),
+    ///         players_max: Some(20).filter(|_| up),
+    ///         version: None,
+    ///         motd_hash: None,
+    ///     })?;
+    /// }
+    ///
+    /// let report = history.report("example.com", Resolution::Raw, start, start + 300)?;
+    ///
+    /// assert_eq!(report.samples, 6);
+    /// // Up for 180 of the 300 seconds between the first and last ping.
+    /// assert_eq!(report.availability, Some(0.6));
+    /// assert_eq!(
+    ///     report.outages,
+    ///     [Outage {
+    ///         start: start + 120,
+    ///         end: Some(start + 240),
+    ///         duration: Duration::from_secs(120),
+    ///     }],
+    /// );
+    /// assert_eq!(report.mttr, Some(Duration::from_secs(120)));
+    /// assert_eq!(report.latency_p50, Some(Duration::from_millis(20)));
+    /// assert_eq!(report.latency_p95, Some(Duration::from_millis(40)));
+    /// assert_eq!(report.peak_players, Some(8));
+    /// assert_eq!(report.peak_players_at, Some(start + 60));
+    ///
+    /// let markdown = report.to_markdown();
+    /// assert!(markdown.contains("| Availability | 60.000% |"));
+    /// assert!(markdown.contains("| 2023-11-14 22:15 UTC | 2023-11-14 22:17 UTC | 2m 0s |"));
+    /// # Ok::<(), mcping::Error>(())
+    /// ```
+    pub fn report(
+        &self,
+        target: &str,
+        resolution: Resolution,
+        from: u64,
+        to: u64,
+    ) -> Result<Report, Error> {
+        let points = self.range(target, resolution, from, to)?;
+        Ok(Report::new(target, from, to, &points))
+    }
+}
+
+/// The fraction of the time between the first and last point that the server
+/// was up, weighing each point by how long it lasted.
+fn availability(points: &[Point]) -> Option<f64> {
+    let fraction = |point: &Point| point.up as f64 / point.samples.max(1) as f64;
+
+    let (up, total) = points.windows(2).fold((0.0, 0.0), |(up, total), pair| {
+        let seconds = (pair[1].timestamp - pair[0].timestamp) as f64;
+        (up + seconds * fraction(&pair[0]), total + seconds)
+    });
+
+    if total > 0.0 {
+        Some(up / total)
+    } else {
+        // All points are at the same time, so count pings instead.
+        let samples: u64 = points.iter().map(|point| point.samples).sum();
+        let up: u64 = points.iter().map(|point| point.up).sum();
+        if samples == 0 {
+            None
+        } else {
+            Some(up as f64 / samples as f64)
+        }
+    }
+}
+
+/// The nearest-rank percentile of sorted values.
+fn percentile(sorted: &[Duration], percent: usize) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+/// Format a duration as hours, minutes and seconds, such as `1h 5m 0s`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, s) => format!("{}h {}m {}s", h, m, s),
+    }
+}
@@ -0,0 +1,393 @@
+//! Retrying pings that fail with transient errors.
+
+use rand::Rng;
+use std::{
+    io,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{Error, ErrorKind, Pingable};
+
+/// How a failed ping is retried.
+///
+/// The whole ping is retried, from the DNS lookup to the last packet, so this
+/// applies to both editions.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::{Backoff, ErrorKind, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy {
+///     max_attempts: 5,
+///     backoff: Backoff::Constant(Duration::from_millis(250)),
+///     deadline: Some(Duration::from_secs(10)),
+///     retry_on: vec![ErrorKind::TimedOut],
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: usize,
+    /// How long to wait between attempts.
+    pub backoff: Backoff,
+    /// How the wait between attempts is randomized.
+    pub jitter: Jitter,
+    /// How long after the first attempt started to give up, cutting short
+    /// the attempt running then.
+    pub deadline: Option<Duration>,
+    /// The kinds of errors worth trying again after.
+    pub retry_on: Vec<ErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(100),
+                multiplier: 2,
+                max: Duration::from_secs(5),
+            },
+            jitter: Jitter::Full,
+            deadline: None,
+            retry_on: vec![
+                ErrorKind::DnsLookupFailed,
+                ErrorKind::TimedOut,
+                ErrorKind::ConnectionRefused,
+                ErrorKind::ConnectionReset,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt.
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The wait before the attempt after `attempt`, counting from 1.
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let delay = self.backoff.delay(attempt);
+
+        match self.jitter {
+            Jitter::None => delay,
+            Jitter::Full => delay.mul_f64(rand::thread_rng().gen()),
+            Jitter::Equal => delay / 2 + (delay / 2).mul_f64(rand::thread_rng().gen()),
+        }
+    }
+
+    /// Whether another attempt should be made after `error`.
+    ///
+    /// `attempts` is the number of attempts made so far, and `elapsed` is the
+    /// time since the first one started plus the wait before the next one.
+    pub(crate) fn should_retry(&self, error: &Error, attempts: usize, elapsed: Duration) -> bool {
+        attempts < self.max_attempts
+            && self.retry_on.contains(&error.kind())
+            && self.deadline.is_none_or(|deadline| elapsed < deadline)
+    }
+}
+
+/// The curve the wait between attempts follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backoff {
+    /// Always wait the same amount of time.
+    Constant(Duration),
+    /// Wait `initial`, multiplying the wait by `multiplier` after every
+    /// attempt, up to `max`.
+    Exponential {
+        initial: Duration,
+        multiplier: u32,
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    fn delay(&self, attempt: usize) -> Duration {
+        match *self {
+            Backoff::Constant(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                multiplier,
+                max,
+            } => {
+                let exponent = attempt.saturating_sub(1).min(u32::MAX as usize) as u32;
+
+                multiplier
+                    .checked_pow(exponent)
+                    .and_then(|factor| initial.checked_mul(factor))
+                    .map_or(max, |delay| delay.min(max))
+            }
+        }
+    }
+}
+
+/// How the wait between attempts is randomized, so that many clients retrying
+/// at once don't do so in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Jitter {
+    /// Wait exactly as long as the backoff says.
+    None,
+    /// Wait anywhere between nothing and the backoff.
+    Full,
+    /// Wait at least half the backoff, and at most all of it.
+    Equal,
+}
+
+/// The outcome of one attempt of a retried ping.
+#[derive(Debug)]
+pub struct Attempt {
+    /// How long after the first attempt this one started.
+    pub started: Duration,
+    /// How long the attempt took.
+    pub duration: Duration,
+    /// The error the attempt failed with, if it did.
+    pub error: Option<Error>,
+}
+
+/// Configuration for retrying another pingable according to a policy.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{Java, Retry, RetryPolicy};
+///
+/// let (latency, retried) = mcping::get_status(Retry {
+///     pingable: Java {
+///         server_address: "mc.hypixel.net".into(),
+///         ..Default::default()
+///     },
+///     policy: RetryPolicy::default(),
+/// })?;
+///
+/// println!("answered after {} attempts", retried.attempts.len());
+/// # Ok::<(), mcping::Error>(())
+/// ```
+///
+/// Every attempt is reported even when the first error isn't worth retrying,
+/// unless the policy only makes one attempt:
+///
+/// ```
+/// use mcping::{Error, ErrorKind, Java, Retry, RetryPolicy};
+///
+/// let err = mcping::get_status(Retry {
+///     pingable: Java {
+///         server_address: "not an address:port".into(),
+///         ..Default::default()
+///     },
+///     policy: RetryPolicy::default(),
+/// })
+/// .unwrap_err();
+///
+/// assert_eq!(err.kind(), ErrorKind::InvalidAddress);
+/// match err {
+///     Error::RetriesExhausted(attempts) => assert_eq!(attempts.len(), 1),
+///     err => panic!("unexpected error: {}", err),
+/// }
+///
+/// let err = mcping::get_status(Retry {
+///     pingable: Java {
+///         server_address: "not an address:port".into(),
+///         ..Default::default()
+///     },
+///     policy: RetryPolicy::never(),
+/// })
+/// .unwrap_err();
+///
+/// assert!(matches!(err, Error::InvalidAddress));
+/// ```
+///
+/// The deadline bounds the whole ping, even an attempt that never finishes:
+///
+/// ```
+/// use mcping::{ErrorKind, Java, Retry, RetryPolicy};
+/// use std::{
+///     net::TcpListener,
+///     time::{Duration, Instant},
+/// };
+///
+/// // A server that accepts connections but never answers.
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+///
+/// let start = Instant::now();
+/// let err = mcping::get_status(Retry {
+///     pingable: Java {
+///         server_address: listener.local_addr()?.to_string(),
+///         timeout: None,
+///         ..Default::default()
+///     },
+///     policy: RetryPolicy {
+///         deadline: Some(Duration::from_millis(200)),
+///         ..Default::default()
+///     },
+/// })
+/// .unwrap_err();
+///
+/// assert_eq!(err.kind(), ErrorKind::TimedOut);
+/// assert!(start.elapsed() < Duration::from_secs(2));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Retry<P> {
+    /// The configuration of each attempt.
+    pub pingable: P,
+    /// How failed attempts are retried.
+    pub policy: RetryPolicy,
+}
+
+/// The response of a retried ping, with the outcome of every attempt.
+#[derive(Debug)]
+pub struct Retried<R> {
+    /// The response of the successful attempt.
+    pub response: R,
+    /// Every attempt, the last of which succeeded.
+    ///
+    /// This is empty when the response came from a cache.
+    pub attempts: Vec<Attempt>,
+}
+
+impl<P> Pingable for Retry<P>
+where
+    P: Pingable + Clone + Send + 'static,
+    P::Response: Send + 'static,
+{
+    type Response = Retried<P::Response>;
+
+    fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let pingable = self.pingable;
+
+        retry(&self.policy, || {
+            let pingable = pingable.clone();
+            move || pingable.ping()
+        })
+        .map(|((latency, response), attempts)| (latency, Retried { response, attempts }))
+    }
+}
+
+/// Make attempts created by `attempt` until one succeeds or the policy gives
+/// up.
+///
+/// If every attempt failed, the error is [`Error::RetriesExhausted`], even
+/// when the first error wasn't worth retrying, unless the policy only makes
+/// one attempt. With a deadline, each attempt runs on its own thread, which
+/// is left to finish in the background if the deadline passes.
+pub(crate) fn retry<T, F>(
+    policy: &RetryPolicy,
+    mut attempt: impl FnMut() -> F,
+) -> Result<(T, Vec<Attempt>), Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    let mut attempts = Attempts::new();
+
+    loop {
+        let started = Instant::now();
+        let result = match attempts.remaining(policy) {
+            Some(remaining) => within(remaining, attempt()),
+            None => attempt()(),
+        };
+
+        match attempts.record(policy, started, result) {
+            Next::Done(result) => return result,
+            Next::Wait(delay) => thread::sleep(delay),
+        }
+    }
+}
+
+/// Run `attempt` on its own thread, failing if it takes longer than `limit`.
+fn within<T, F>(limit: Duration, attempt: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(attempt());
+    });
+
+    rx.recv_timeout(limit)
+        .unwrap_or_else(|_| Err(deadline_passed()))
+}
+
+/// The error of an attempt cut short by the deadline.
+pub(crate) fn deadline_passed() -> Error {
+    io::Error::new(io::ErrorKind::TimedOut, "the retry deadline passed").into()
+}
+
+/// What to do after an attempt.
+pub(crate) enum Next<T> {
+    Done(Result<(T, Vec<Attempt>), Error>),
+    Wait(Duration),
+}
+
+/// The bookkeeping of a retried ping, shared by both runtimes.
+pub(crate) struct Attempts {
+    first: Instant,
+    attempts: Vec<Attempt>,
+}
+
+impl Attempts {
+    pub(crate) fn new() -> Self {
+        Self {
+            first: Instant::now(),
+            attempts: Vec::new(),
+        }
+    }
+
+    /// The time left before the deadline, if there is one.
+    pub(crate) fn remaining(&self, policy: &RetryPolicy) -> Option<Duration> {
+        policy
+            .deadline
+            .map(|deadline| deadline.saturating_sub(self.first.elapsed()))
+    }
+
+    /// Record the outcome of an attempt that began at `started`.
+    pub(crate) fn record<T>(
+        &mut self,
+        policy: &RetryPolicy,
+        started: Instant,
+        result: Result<T, Error>,
+    ) -> Next<T> {
+        let mut attempt = Attempt {
+            started: started - self.first,
+            duration: started.elapsed(),
+            error: None,
+        };
+
+        let error = match result {
+            Ok(value) => {
+                self.attempts.push(attempt);
+                return Next::Done(Ok((value, std::mem::take(&mut self.attempts))));
+            }
+            Err(error) => error,
+        };
+
+        let delay = policy.delay(self.attempts.len() + 1);
+        let elapsed = self.first.elapsed() + delay;
+
+        if policy.should_retry(&error, self.attempts.len() + 1, elapsed) {
+            attempt.error = Some(error);
+            self.attempts.push(attempt);
+            return Next::Wait(delay);
+        }
+
+        if policy.max_attempts <= 1 {
+            return Next::Done(Err(error));
+        }
+
+        attempt.error = Some(error);
+        self.attempts.push(attempt);
+        Next::Done(Err(Error::RetriesExhausted(std::mem::take(
+            &mut self.attempts,
+        ))))
+    }
+}
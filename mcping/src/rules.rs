@@ -0,0 +1,611 @@
+//! Alerting on conditions that hold over the results of repeated pings.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Error, ServerStatus};
+
+/// Something about a server worth alerting on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// The ping failed.
+    Down,
+    /// The latency is above the given duration.
+    LatencyAbove(Duration),
+    /// The players online are at least the given fraction of the maximum.
+    PlayersAtLeast(f64),
+    /// The version name or protocol differs from the previous response.
+    VersionChanged,
+    /// The protocol version isn't the given one (ex: 765 for 1.20.4).
+    ProtocolIsNot(i64),
+}
+
+impl Condition {
+    /// Whether the condition holds for a ping result, given the previous
+    /// successful response.
+    fn holds(
+        &self,
+        result: Result<&ServerStatus, &Error>,
+        previous: Option<&ServerStatus>,
+    ) -> bool {
+        let status = match result {
+            Ok(status) => status,
+            Err(_) => return *self == Condition::Down,
+        };
+
+        match *self {
+            Condition::Down => false,
+            Condition::LatencyAbove(max) => status.latency > max,
+            Condition::PlayersAtLeast(fraction) => {
+                match (status.players_online, status.players_max) {
+                    (Some(online), Some(max)) if max > 0 => online as f64 >= fraction * max as f64,
+                    _ => false,
+                }
+            }
+            Condition::VersionChanged => previous.is_some_and(|previous| {
+                previous.version_name != status.version_name || previous.protocol != status.protocol
+            }),
+            Condition::ProtocolIsNot(protocol) => status.protocol != Some(protocol),
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Down => f.write_str("the server is down"),
+            Condition::LatencyAbove(max) => write!(f, "latency is above {:?}", max),
+            Condition::PlayersAtLeast(fraction) => {
+                write!(
+                    f,
+                    "players online are at {:.0}% of the maximum",
+                    fraction * 100.0
+                )
+            }
+            Condition::VersionChanged => f.write_str("the version changed"),
+            Condition::ProtocolIsNot(protocol) => write!(f, "the protocol isn't {}", protocol),
+        }
+    }
+}
+
+/// A named condition, and how long it has to hold or clear before the alert
+/// changes state.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::{Condition, Rule};
+/// use std::time::Duration;
+///
+/// let slow = Rule {
+///     name: "slow".into(),
+///     condition: Condition::LatencyAbove(Duration::from_millis(200)),
+///     hold_for: Duration::from_secs(5 * 60),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    /// The name alerts of the rule are sent with.
+    pub name: String,
+    /// What the rule alerts on.
+    pub condition: Condition,
+    /// How long the condition has to hold for every ping before the alert
+    /// fires.
+    pub hold_for: Duration,
+    /// How long the condition has to be clear for every ping before the
+    /// alert resolves.
+    pub resolve_after: Duration,
+    /// How long after resolving the alert can't fire again.
+    pub cooldown: Duration,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            condition: Condition::Down,
+            hold_for: Duration::ZERO,
+            resolve_after: Duration::from_secs(60),
+            cooldown: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Whether an alert started or stopped.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// A notification that a rule started or stopped matching a server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    /// The name of the rule.
+    pub rule: String,
+    /// The server the rule matched.
+    pub target: String,
+    pub state: AlertState,
+    /// A description of the condition.
+    pub condition: String,
+    /// When the alert changed state, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl std::fmt::Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self.state {
+            AlertState::Firing => "firing",
+            AlertState::Resolved => "resolved",
+        };
+
+        write!(
+            f,
+            "[{}] {} on {}: {}",
+            state, self.rule, self.target, self.condition
+        )
+    }
+}
+
+/// Somewhere alerts are delivered.
+pub trait AlertSink {
+    /// Deliver an alert.
+    fn send(&mut self, alert: &Alert) -> Result<(), Error>;
+}
+
+/// A sink writing every alert as a line of text, to stderr by default.
+#[derive(Debug)]
+pub struct LogSink<W = io::Stderr> {
+    writer: W,
+}
+
+impl<W: Write> LogSink<W> {
+    /// Write alerts to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl Default for LogSink {
+    fn default() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W: Write> AlertSink for LogSink<W> {
+    fn send(&mut self, alert: &Alert) -> Result<(), Error> {
+        writeln!(self.writer, "{}", alert)?;
+        Ok(())
+    }
+}
+
+/// A sink posting every alert as JSON to an HTTP endpoint.
+///
+/// Only plain `http://` URLs are supported. A response with a status outside
+/// of 2xx fails the delivery.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::WebhookSink;
+///
+/// let sink = WebhookSink {
+///     url: "http://127.0.0.1:9000/alerts".into(),
+///     headers: vec![("Authorization".into(), "Bearer secret".into())],
+///     ..Default::default()
+/// };
+/// ```
+///
+/// Every alert is one request, with the alert as its body:
+///
+/// ```
+/// use mcping::{Alert, AlertSink, AlertState, WebhookSink};
+/// use std::{
+///     io::{BufRead, BufReader, Read, Write},
+///     net::TcpListener,
+///     thread,
+/// };
+///
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// let url = format!("http://{}/alerts", listener.local_addr()?);
+///
+/// let server = thread::spawn(move || -> std::io::Result<(Vec<String>, String)> {
+///     let (stream, _) = listener.accept()?;
+///     let mut reader = BufReader::new(stream);
+///
+///     let mut head = Vec::new();
+///     loop {
+///         let mut line = String::new();
+///         reader.read_line(&mut line)?;
+///         if line == "\r\n" {
+///             break;
+///         }
+///         head.push(line.trim_end().to_string());
+///     }
+///
+///     let length = head
+///         .iter()
+///         .find_map(|line| line.strip_prefix("Content-Length: "))
+///         .and_then(|length| length.parse().ok())
+///         .unwrap_or(0);
+///     let mut body = vec![0; length];
+///     reader.read_exact(&mut body)?;
+///
+///     reader.get_mut().write_all(b"HTTP/1.1 204 No Content\r\n\r\n")?;
+///     Ok((head, String::from_utf8_lossy(&body).into_owned()))
+/// });
+///
+/// let mut sink = WebhookSink {
+///     url,
+///     headers: vec![("Authorization".into(), "Bearer secret".into())],
+///     ..Default::default()
+/// };
+/// sink.send(&Alert {
+///     rule: "slow".into(),
+///     target: "mc.example.com".into(),
+///     state: AlertState::Firing,
+///     condition: "latency above 200ms".into(),
+///     timestamp: 1_600_000_000,
+/// })?;
+///
+/// let (head, body) = server.join().unwrap()?;
+/// assert_eq!(head[0], "POST /alerts HTTP/1.1");
+/// assert!(head.iter().any(|line| line == "Content-Type: application/json"));
+/// assert!(head.iter().any(|line| line == "Authorization: Bearer secret"));
+/// assert_eq!(
+///     serde_json::from_str::<serde_json::Value>(&body)?,
+///     serde_json::json!({
+///         "rule": "slow",
+///         "target": "mc.example.com",
+///         "state": "firing",
+///         "condition": "latency above 200ms",
+///         "timestamp": 1_600_000_000,
+///     })
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct WebhookSink {
+    /// The URL to post alerts to.
+    pub url: String,
+    /// Extra headers sent with every request.
+    pub headers: Vec<(String, String)>,
+    /// The timeout for connecting and for each read and write.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for WebhookSink {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            headers: Vec::new(),
+            timeout: Some(Duration::from_secs(5)),
+        }
+    }
+}
+
+impl WebhookSink {
+    /// Split the URL up into the authority and the path.
+    fn target(&self) -> Result<(&str, &str), Error> {
+        let rest = self
+            .url
+            .strip_prefix("http://")
+            .ok_or(Error::InvalidAddress)?;
+
+        Ok(match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        })
+    }
+}
+
+impl AlertSink for WebhookSink {
+    fn send(&mut self, alert: &Alert) -> Result<(), Error> {
+        let (authority, path) = self.target()?;
+        let body = serde_json::to_vec(alert)?;
+
+        let addr = if authority.contains(':') {
+            authority.to_socket_addrs()
+        } else {
+            (authority, 80).to_socket_addrs()
+        }?
+        .next()
+        .ok_or(Error::DnsLookupFailed)?;
+
+        let mut stream = match self.timeout {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout)?,
+            None => TcpStream::connect(addr)?,
+        };
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            path,
+            authority,
+            body.len()
+        );
+        for (name, value) in &self.headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+
+        let mut request = request.into_bytes();
+        request.extend_from_slice(&body);
+        stream.write_all(&request)?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line)?;
+
+        match status_line.split_whitespace().nth(1) {
+            Some(status) if status.starts_with('2') => Ok(()),
+            Some(status) => {
+                Err(io::Error::other(format!("the webhook answered with status {}", status)).into())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the webhook sent an invalid response",
+            )
+            .into()),
+        }
+    }
+}
+
+/// Evaluates rules over the ping results of servers and notifies sinks when
+/// alerts fire or resolve.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{Condition, LogSink, Rule, RuleEngine, WebhookSink};
+/// use std::time::Duration;
+///
+/// let mut engine = RuleEngine::new(vec![
+///     Rule {
+///         name: "down".into(),
+///         condition: Condition::Down,
+///         hold_for: Duration::from_secs(60),
+///         ..Default::default()
+///     },
+///     Rule {
+///         name: "full".into(),
+///         condition: Condition::PlayersAtLeast(0.95),
+///         ..Default::default()
+///     },
+/// ]);
+/// engine.add_sink(LogSink::default());
+/// engine.add_sink(WebhookSink {
+///     url: "http://127.0.0.1:9000/alerts".into(),
+///     ..Default::default()
+/// });
+///
+/// loop {
+///     let status = mcping::get_status_any(mcping::Java {
+///         server_address: "mc.hypixel.net".into(),
+///         timeout: Some(Duration::from_secs(5)),
+///         ..Default::default()
+///     });
+///
+///     let (_, errors) = engine.observe("mc.hypixel.net", status.as_ref());
+///     for error in errors {
+///         eprintln!("failed to deliver an alert: {}", error);
+///     }
+///     std::thread::sleep(Duration::from_secs(30));
+/// }
+/// ```
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    sinks: Vec<Box<dyn AlertSink + Send>>,
+    targets: HashMap<String, TargetState>,
+}
+
+#[derive(Default)]
+struct TargetState {
+    /// The last successful response of the server.
+    previous: Option<ServerStatus>,
+    /// The state of every rule, in the same order as the rules.
+    rules: Vec<RuleState>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RuleState {
+    firing: bool,
+    /// Since when the condition has held for every ping.
+    holding_since: Option<Instant>,
+    /// Since when the condition has been clear for every ping.
+    clear_since: Option<Instant>,
+    resolved_at: Option<Instant>,
+}
+
+impl RuleEngine {
+    /// Create an engine evaluating `rules`, with no sinks.
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            sinks: Vec::new(),
+            targets: HashMap::new(),
+        }
+    }
+
+    /// Deliver alerts to `sink` as well.
+    pub fn add_sink(&mut self, sink: impl AlertSink + Send + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// The rules being evaluated.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Evaluate every rule against the result of pinging `target`, sending
+    /// the alerts that fired or resolved to every sink.
+    ///
+    /// Returns the alerts, along with every delivery that failed. Every sink
+    /// is sent every alert even if some deliveries fail.
+    pub fn observe(
+        &mut self,
+        target: &str,
+        result: Result<&ServerStatus, &Error>,
+    ) -> (Vec<Alert>, Vec<Error>) {
+        self.observe_at(target, result, Instant::now())
+    }
+
+    /// Like [`RuleEngine::observe`], for a ping made at `now`, which must not
+    /// be earlier than the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{
+    ///     Alert, AlertSink, AlertState, Chat, Condition, Edition, Error, Rule, RuleEngine,
+    ///     ServerStatus,
+    /// };
+    /// use std::time::{Duration, Instant};
+    ///
+    /// struct Unreachable;
+    ///
+    /// impl AlertSink for Unreachable {
+    ///     fn send(&mut self, _: &Alert) -> Result<(), Error> {
+    ///         Err(std::io::Error::other("unreachable").into())
+    ///     }
+    /// }
+    ///
+    /// let mut engine = RuleEngine::new(vec![Rule {
+    ///     name: "down".into(),
+    ///     condition: Condition::Down,
+    ///     hold_for: Duration::from_secs(60),
+    ///     resolve_after: Duration::from_secs(60),
+    ///     cooldown: Duration::from_secs(300),
+    /// }]);
+    /// engine.add_sink(Unreachable);
+    ///
+    /// let status = ServerStatus {
+    ///     edition: Edition::Java,
+    ///     version_name: "1.20.4".into(),
+    ///     protocol: Some(765),
+    ///     players_online: Some(1),
+    ///     players_max: Some(20),
+    ///     motd: Chat::String("A server".into()),
+    ///     icon: None,
+    ///     sample: None,
+    ///     latency: Duration::from_millis(40),
+    ///     metadata: None,
+    /// };
+    ///
+    /// let start = Instant::now();
+    /// let mut ping = |seconds, up| {
+    ///     let result = if up { Ok(&status) } else { Err(&Error::DnsLookupFailed) };
+    ///     let at = start + Duration::from_secs(seconds);
+    ///     let (alerts, errors) = engine.observe_at("example.com", result, at);
+    ///     // Failed deliveries don't lose the alerts.
+    ///     assert_eq!(errors.len(), alerts.len());
+    ///     alerts.into_iter().map(|alert| alert.state).collect::<Vec<_>>()
+    /// };
+    ///
+    /// // Pending until the server has been down for a minute.
+    /// assert_eq!(ping(0, false), []);
+    /// assert_eq!(ping(30, false), []);
+    /// assert_eq!(ping(60, false), [AlertState::Firing]);
+    ///
+    /// // Resolved once it has been up for a minute.
+    /// assert_eq!(ping(90, true), []);
+    /// assert_eq!(ping(150, true), [AlertState::Resolved]);
+    ///
+    /// // Cooling down for five minutes after resolving.
+    /// assert_eq!(ping(160, false), []);
+    /// assert_eq!(ping(220, false), []);
+    /// assert_eq!(ping(450, false), [AlertState::Firing]);
+    /// ```
+    pub fn observe_at(
+        &mut self,
+        target: &str,
+        result: Result<&ServerStatus, &Error>,
+        now: Instant,
+    ) -> (Vec<Alert>, Vec<Error>) {
+        let state = self.targets.entry(target.to_string()).or_default();
+        state.rules.resize(self.rules.len(), RuleState::default());
+
+        let mut alerts = Vec::new();
+
+        for (rule, rule_state) in self.rules.iter().zip(&mut state.rules) {
+            let holds = rule.condition.holds(result, state.previous.as_ref());
+
+            if let Some(alert_state) = rule_state.update(rule, holds, now) {
+                alerts.push(Alert {
+                    rule: rule.name.clone(),
+                    target: target.to_string(),
+                    state: alert_state,
+                    condition: rule.condition.to_string(),
+                    timestamp: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                });
+            }
+        }
+
+        if let Ok(status) = result {
+            state.previous = Some(status.clone());
+        }
+
+        let mut errors = Vec::new();
+        for alert in &alerts {
+            for sink in &mut self.sinks {
+                if let Err(e) = sink.send(alert) {
+                    errors.push(e);
+                }
+            }
+        }
+
+        (alerts, errors)
+    }
+}
+
+impl std::fmt::Debug for RuleEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RuleEngine")
+            .field("rules", &self.rules)
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+impl RuleState {
+    /// Account for whether the condition held at `now`, returning the state
+    /// the alert changed to, if it did.
+    fn update(&mut self, rule: &Rule, holds: bool, now: Instant) -> Option<AlertState> {
+        if holds {
+            self.clear_since = None;
+            let since = *self.holding_since.get_or_insert(now);
+
+            let cooled_down = self
+                .resolved_at
+                .is_none_or(|resolved| now - resolved >= rule.cooldown);
+
+            if !self.firing && now - since >= rule.hold_for && cooled_down {
+                self.firing = true;
+                return Some(AlertState::Firing);
+            }
+        } else {
+            self.holding_since = None;
+            let since = *self.clear_since.get_or_insert(now);
+
+            if self.firing && now - since >= rule.resolve_after {
+                self.firing = false;
+                self.resolved_at = Some(now);
+                return Some(AlertState::Resolved);
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,107 @@
+//! A common view over the responses of both editions.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::{BedrockResponse, Chat, JavaResponse, PingMetadata, Player};
+
+/// The edition of Minecraft a server is running.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Edition {
+    Java,
+    Bedrock,
+}
+
+impl std::fmt::Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Edition::Java => f.write_str("Java"),
+            Edition::Bedrock => f.write_str("Bedrock"),
+        }
+    }
+}
+
+/// The status of a server, regardless of the edition it is running.
+///
+/// This can be created from the latency and response of either edition, or
+/// retrieved directly with [`get_status_any`](crate::get_status_any).
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::ServerStatus;
+///
+/// let (latency, response) = mcping::get_status(mcping::Bedrock {
+///     server_address: "play.nethergames.org".into(),
+///     ..Default::default()
+/// })?;
+///
+/// let status = ServerStatus::from((latency, response));
+/// println!("{}/{}", status.players_online.unwrap_or(0), status.players_max.unwrap_or(0));
+/// # Ok::<(), mcping::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerStatus {
+    /// The edition of the server.
+    pub edition: Edition,
+    /// The name of the version the server is running.
+    pub version_name: String,
+    /// The protocol version of the server.
+    pub protocol: Option<i64>,
+    /// The number of players online.
+    pub players_online: Option<i64>,
+    /// The maximum number of players that could be online at once.
+    pub players_max: Option<i64>,
+    /// The server's Message Of The Day.
+    ///
+    /// Bedrock servers only send legacy formatted text, which is kept as a
+    /// [`Chat::String`].
+    pub motd: Chat,
+    /// The server icon (a Base64-encoded PNG image).
+    ///
+    /// Only Java servers send an icon.
+    pub icon: Option<String>,
+    /// A preview of which players are online.
+    ///
+    /// Only Java servers send a sample.
+    pub sample: Option<Vec<Player>>,
+    /// The latency to the server.
+    pub latency: Duration,
+    /// How the ping went, if the response came from one.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub metadata: Option<PingMetadata>,
+}
+
+impl From<(Duration, JavaResponse)> for ServerStatus {
+    fn from((latency, response): (Duration, JavaResponse)) -> Self {
+        Self {
+            edition: Edition::Java,
+            version_name: response.version.name,
+            protocol: Some(response.version.protocol),
+            players_online: Some(response.players.online),
+            players_max: Some(response.players.max),
+            motd: response.description,
+            icon: response.favicon,
+            sample: response.players.sample,
+            latency,
+            metadata: response.metadata,
+        }
+    }
+}
+
+impl From<(Duration, BedrockResponse)> for ServerStatus {
+    fn from((latency, response): (Duration, BedrockResponse)) -> Self {
+        Self {
+            edition: Edition::Bedrock,
+            version_name: response.version_name,
+            protocol: response.protocol_version,
+            players_online: response.players_online,
+            players_max: response.players_max,
+            motd: Chat::String(response.motd_1),
+            icon: None,
+            sample: None,
+            latency,
+            metadata: response.metadata,
+        }
+    }
+}
@@ -0,0 +1,644 @@
+//! Recording ping results in an SQLite database.
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{fnv1a, Error, ServerStatus};
+
+/// How long records are kept at each resolution, or `None` to keep them
+/// forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Retention {
+    pub raw: Option<Duration>,
+    pub five_minutes: Option<Duration>,
+    pub hourly: Option<Duration>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        const DAY: u64 = 24 * 60 * 60;
+
+        Self {
+            raw: Some(Duration::from_secs(7 * DAY)),
+            five_minutes: Some(Duration::from_secs(90 * DAY)),
+            hourly: None,
+        }
+    }
+}
+
+/// The granularity records are queried at.
+///
+/// Every record is kept as-is, and also added to a five-minute and an hourly
+/// summary, which outlive the records according to the [`Retention`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Raw,
+    FiveMinutes,
+    Hourly,
+}
+
+impl Resolution {
+    /// The length of a bucket in seconds, if records are summarized.
+    fn seconds(self) -> Option<i64> {
+        match self {
+            Resolution::Raw => None,
+            Resolution::FiveMinutes => Some(5 * 60),
+            Resolution::Hourly => Some(60 * 60),
+        }
+    }
+}
+
+/// The result of a single ping, as stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// When the ping was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The server that was pinged.
+    pub target: String,
+    /// Whether the ping succeeded.
+    pub up: bool,
+    pub latency: Option<Duration>,
+    pub players_online: Option<i64>,
+    pub players_max: Option<i64>,
+    pub version: Option<String>,
+    /// A hash of the MOTD, to notice when it changes.
+    pub motd_hash: Option<String>,
+}
+
+impl Record {
+    /// The record of pinging `target` just now.
+    pub fn new(target: &str, result: Result<&ServerStatus, &Error>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        match result {
+            Ok(status) => Self {
+                timestamp,
+                target: target.to_string(),
+                up: true,
+                latency: Some(status.latency),
+                players_online: status.players_online,
+                players_max: status.players_max,
+                version: Some(status.version_name.clone()),
+                motd_hash: serde_json::to_vec(&status.motd)
+                    .ok()
+                    .map(|motd| format!("{:016x}", fnv1a(&motd))),
+            },
+            Err(_) => Self {
+                timestamp,
+                target: target.to_string(),
+                up: false,
+                latency: None,
+                players_online: None,
+                players_max: None,
+                version: None,
+                motd_hash: None,
+            },
+        }
+    }
+}
+
+/// The records of a server in one bucket of time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    /// The start of the bucket, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The number of pings made.
+    pub samples: u64,
+    /// The number of pings that succeeded.
+    pub up: u64,
+    pub latency_mean: Option<Duration>,
+    pub latency_min: Option<Duration>,
+    pub latency_max: Option<Duration>,
+    pub players_online_mean: Option<f64>,
+    pub players_online_max: Option<i64>,
+    /// The last maximum number of players reported.
+    pub players_max: Option<i64>,
+    /// The last version reported.
+    pub version: Option<String>,
+    /// The hash of the last MOTD reported.
+    pub motd_hash: Option<String>,
+}
+
+/// Statistics over the records of a server in a range of time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    /// The number of pings made.
+    pub samples: u64,
+    /// The number of pings that succeeded.
+    pub up: u64,
+    pub latency_mean: Option<Duration>,
+    pub latency_min: Option<Duration>,
+    pub latency_max: Option<Duration>,
+    pub players_online_mean: Option<f64>,
+    pub players_online_max: Option<i64>,
+}
+
+impl Summary {
+    /// The fraction of pings that succeeded, if any were made.
+    pub fn uptime(&self) -> Option<f64> {
+        if self.samples == 0 {
+            None
+        } else {
+            Some(self.up as f64 / self.samples as f64)
+        }
+    }
+}
+
+/// A history of ping results, stored in an SQLite database.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mcping::{History, Java, Record, Resolution, Retention};
+/// use std::time::{SystemTime, UNIX_EPOCH};
+///
+/// let history = History::open("history.sqlite3", Retention::default())?;
+///
+/// let status = mcping::get_status_any(Java {
+///     server_address: "mc.hypixel.net".into(),
+///     ..Default::default()
+/// });
+/// history.record(&Record::new("mc.hypixel.net", status.as_ref()))?;
+///
+/// let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+/// let day_ago = now - 24 * 60 * 60;
+/// for point in history.range("mc.hypixel.net", Resolution::FiveMinutes, day_ago, u64::MAX)? {
+///     println!("{}: {:?} players", point.timestamp, point.players_online_max);
+/// }
+/// # Ok::<(), mcping::Error>(())
+/// ```
+pub struct History {
+    conn: Mutex<Connection>,
+    retention: Retention,
+    last_pruned: Mutex<Instant>,
+}
+
+/// The columns of a bucket, selected from the raw records.
+const RAW_BUCKETS: &str = "SELECT target, timestamp AS bucket, 1 AS samples, up,
+    COALESCE(latency_us, 0) AS latency_sum, latency_us IS NOT NULL AS latency_count,
+    latency_us AS latency_min, latency_us AS latency_max,
+    COALESCE(players_online, 0) AS players_sum, players_online IS NOT NULL AS players_count,
+    players_online AS players_online_max, players_max, version, motd_hash
+    FROM records";
+
+impl History {
+    /// Open the database at `path`, creating it if needed.
+    pub fn open(path: impl AsRef<Path>, retention: Retention) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?, retention)
+    }
+
+    /// Open a database that only lives in memory.
+    pub fn in_memory(retention: Retention) -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?, retention)
+    }
+
+    fn with_connection(conn: Connection, retention: Retention) -> Result<Self, Error> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS records (
+                timestamp INTEGER NOT NULL,
+                target TEXT NOT NULL,
+                up INTEGER NOT NULL,
+                latency_us INTEGER,
+                players_online INTEGER,
+                players_max INTEGER,
+                version TEXT,
+                motd_hash TEXT
+            );
+            CREATE INDEX IF NOT EXISTS records_target_timestamp ON records (target, timestamp);
+            CREATE TABLE IF NOT EXISTS buckets (
+                resolution INTEGER NOT NULL,
+                target TEXT NOT NULL,
+                bucket INTEGER NOT NULL,
+                samples INTEGER NOT NULL,
+                up INTEGER NOT NULL,
+                latency_sum INTEGER NOT NULL,
+                latency_count INTEGER NOT NULL,
+                latency_min INTEGER,
+                latency_max INTEGER,
+                players_sum INTEGER NOT NULL,
+                players_count INTEGER NOT NULL,
+                players_online_max INTEGER,
+                players_max INTEGER,
+                version TEXT,
+                motd_hash TEXT,
+                PRIMARY KEY (resolution, target, bucket)
+            );",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            retention,
+            last_pruned: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Store a record, adding it to the summaries of its buckets.
+    ///
+    /// Records past their retention are pruned at most once an hour.
+    pub fn record(&self, record: &Record) -> Result<(), Error> {
+        let timestamp = record.timestamp as i64;
+        let latency = record.latency.map(|latency| latency.as_micros() as i64);
+
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+
+            tx.execute(
+                "INSERT INTO records VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    timestamp,
+                    record.target,
+                    record.up,
+                    latency,
+                    record.players_online,
+                    record.players_max,
+                    record.version,
+                    record.motd_hash,
+                ],
+            )?;
+
+            for resolution in [Resolution::FiveMinutes, Resolution::Hourly] {
+                let seconds = resolution.seconds().unwrap_or(1);
+
+                tx.execute(
+                    "INSERT INTO buckets VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6, ?7, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                    ON CONFLICT (resolution, target, bucket) DO UPDATE SET
+                        samples = samples + 1,
+                        up = up + excluded.up,
+                        latency_sum = latency_sum + excluded.latency_sum,
+                        latency_count = latency_count + excluded.latency_count,
+                        latency_min = COALESCE(MIN(latency_min, excluded.latency_min), latency_min, excluded.latency_min),
+                        latency_max = COALESCE(MAX(latency_max, excluded.latency_max), latency_max, excluded.latency_max),
+                        players_sum = players_sum + excluded.players_sum,
+                        players_count = players_count + excluded.players_count,
+                        players_online_max = COALESCE(MAX(players_online_max, excluded.players_online_max), players_online_max, excluded.players_online_max),
+                        players_max = COALESCE(excluded.players_max, players_max),
+                        version = COALESCE(excluded.version, version),
+                        motd_hash = COALESCE(excluded.motd_hash, motd_hash)",
+                    params![
+                        seconds,
+                        record.target,
+                        timestamp - timestamp.rem_euclid(seconds),
+                        record.up,
+                        latency.unwrap_or(0),
+                        latency.is_some(),
+                        latency,
+                        record.players_online.unwrap_or(0),
+                        record.players_online.is_some(),
+                        record.players_online,
+                        record.players_max,
+                        record.version,
+                        record.motd_hash,
+                    ],
+                )?;
+            }
+
+            tx.commit()?;
+        }
+
+        let mut last_pruned = self.last_pruned.lock().unwrap();
+        if last_pruned.elapsed() >= Duration::from_secs(60 * 60) {
+            *last_pruned = Instant::now();
+            drop(last_pruned);
+            self.prune()?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete the records and summaries past their retention.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{History, Record, Resolution, Retention};
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    ///
+    /// let day = Duration::from_secs(24 * 60 * 60);
+    /// let history = History::in_memory(Retention {
+    ///     raw: Some(day),
+    ///     five_minutes: Some(7 * day),
+    ///     hourly: None,
+    /// })?;
+    ///
+    /// let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    /// for days_ago in [0, 2, 30] {
+    ///     let mut record = Record::new("example.com", Err(&mcping::Error::InvalidAddress));
+    ///     record.timestamp = now - days_ago * day.as_secs();
+    ///     history.record(&record)?;
+    /// }
+    ///
+    /// history.prune()?;
+    ///
+    /// let count = |resolution| -> Result<usize, mcping::Error> {
+    ///     Ok(history.range("example.com", resolution, 0, u64::MAX)?.len())
+    /// };
+    /// assert_eq!(count(Resolution::Raw)?, 1);
+    /// assert_eq!(count(Resolution::FiveMinutes)?, 2);
+    /// assert_eq!(count(Resolution::Hourly)?, 3);
+    /// # Ok::<(), mcping::Error>(())
+    /// ```
+    pub fn prune(&self) -> Result<(), Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let cutoff = |keep: Duration| now.saturating_sub(keep.as_secs() as i64);
+
+        let conn = self.conn.lock().unwrap();
+
+        if let Some(keep) = self.retention.raw {
+            conn.execute("DELETE FROM records WHERE timestamp < ?1", [cutoff(keep)])?;
+        }
+
+        for (resolution, keep) in [
+            (Resolution::FiveMinutes, self.retention.five_minutes),
+            (Resolution::Hourly, self.retention.hourly),
+        ] {
+            if let Some(keep) = keep {
+                conn.execute(
+                    "DELETE FROM buckets WHERE resolution = ?1 AND bucket < ?2",
+                    params![resolution.seconds(), cutoff(keep)],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The records of `target` from `from` up to `to` (both in seconds since
+    /// the Unix epoch, inclusive), at the given resolution.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mcping::{History, Record, Resolution, Retention};
+    /// use std::time::Duration;
+    ///
+    /// let history = History::in_memory(Retention::default())?;
+    ///
+    /// // Three pings in one five-minute bucket, one of which failed, and one
+    /// // in the next.
+    /// let start = 1_699_999_800;
+    /// for &(offset, latency, players) in &[(0, 10, 5), (100, 30, 7), (200, 0, 0), (300, 20, 2)] {
+    ///     let up = latency > 0;
+    ///     history.record(&Record {
+    ///         timestamp: start + offset,
+    ///         target: "example.com".to_string(),
+    ///         up,
+    ///         latency: Some(Duration::from_millis(latency)).filter(|_| up),
+    ///         players_online: Some(players).filter(|_| up),
+    ///         players_max: Some(20).filter(|_| up),
+    ///         version: Some("1.20.4".to_string()).filter(|_| up),
+    ///         motd_hash: None,
+    ///     })?;
+    /// }
+    ///
+    /// let raw = history.range("example.com", Resolution::Raw, start, start + 299)?;
+    /// assert_eq!(raw.len(), 3);
+    ///
+    /// let buckets = history.range("example.com", Resolution::FiveMinutes, 0, u64::MAX)?;
+    /// assert_eq!(buckets.len(), 2);
+    /// assert_eq!((buckets[0].timestamp, buckets[0].samples, buckets[0].up), (start, 3, 2));
+    /// assert_eq!(buckets[0].latency_mean, Some(Duration::from_millis(20)));
+    /// assert_eq!(buckets[0].latency_min, Some(Duration::from_millis(10)));
+    /// assert_eq!(buckets[0].latency_max, Some(Duration::from_millis(30)));
+    /// assert_eq!(buckets[0].players_online_mean, Some(6.0));
+    /// assert_eq!(buckets[0].players_online_max, Some(7));
+    /// assert_eq!((buckets[1].timestamp, buckets[1].samples), (start + 300, 1));
+    ///
+    /// let summary = history.summary("example.com", Resolution::Hourly, 0, u64::MAX)?;
+    /// assert_eq!((summary.samples, summary.up), (4, 3));
+    /// assert_eq!(summary.uptime(), Some(0.75));
+    /// assert_eq!(summary.latency_mean, Some(Duration::from_millis(20)));
+    /// assert_eq!(summary.players_online_max, Some(7));
+    ///
+    /// let mut csv = Vec::new();
+    /// history.export_csv(Some("example.com"), start, start, &mut csv)?;
+    /// assert_eq!(
+    ///     String::from_utf8(csv).unwrap(),
+    ///     "timestamp,target,up,latency_ms,players_online,players_max,version,motd_hash\n\
+    ///      1699999800,example.com,true,10.000,5,20,1.20.4,\n",
+    /// );
+    /// # Ok::<(), mcping::Error>(())
+    /// ```
+    pub fn range(
+        &self,
+        target: &str,
+        resolution: Resolution,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<Point>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM ({}) WHERE target = ?1 AND bucket BETWEEN ?2 AND ?3 ORDER BY bucket",
+            source(resolution)
+        ))?;
+
+        let points = stmt
+            .query_map(params![target, clamp(from), clamp(to)], |row| {
+                Ok(Point {
+                    timestamp: row.get::<_, i64>("bucket")? as u64,
+                    samples: row.get::<_, i64>("samples")? as u64,
+                    up: row.get::<_, i64>("up")? as u64,
+                    latency_mean: mean(row.get("latency_sum")?, row.get("latency_count")?)
+                        .map(micros),
+                    latency_min: row
+                        .get::<_, Option<i64>>("latency_min")?
+                        .map(|us| micros(us as f64)),
+                    latency_max: row
+                        .get::<_, Option<i64>>("latency_max")?
+                        .map(|us| micros(us as f64)),
+                    players_online_mean: mean(row.get("players_sum")?, row.get("players_count")?),
+                    players_online_max: row.get("players_online_max")?,
+                    players_max: row.get("players_max")?,
+                    version: row.get("version")?,
+                    motd_hash: row.get("motd_hash")?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(points)
+    }
+
+    /// Statistics over the records of `target` from `from` up to `to`, at the
+    /// given resolution.
+    pub fn summary(
+        &self,
+        target: &str,
+        resolution: Resolution,
+        from: u64,
+        to: u64,
+    ) -> Result<Summary, Error> {
+        let conn = self.conn.lock().unwrap();
+
+        let summary = conn.query_row(
+            &format!(
+                "SELECT COALESCE(SUM(samples), 0), COALESCE(SUM(up), 0),
+                    SUM(latency_sum), SUM(latency_count), MIN(latency_min), MAX(latency_max),
+                    SUM(players_sum), SUM(players_count), MAX(players_online_max)
+                FROM ({}) WHERE target = ?1 AND bucket BETWEEN ?2 AND ?3",
+                source(resolution)
+            ),
+            params![target, clamp(from), clamp(to)],
+            |row| {
+                Ok(Summary {
+                    samples: row.get::<_, i64>(0)? as u64,
+                    up: row.get::<_, i64>(1)? as u64,
+                    latency_mean: mean(row.get(2)?, row.get(3)?).map(micros),
+                    latency_min: row.get::<_, Option<i64>>(4)?.map(|us| micros(us as f64)),
+                    latency_max: row.get::<_, Option<i64>>(5)?.map(|us| micros(us as f64)),
+                    players_online_mean: mean(row.get(6)?, row.get(7)?),
+                    players_online_max: row.get(8)?,
+                })
+            },
+        )?;
+
+        Ok(summary)
+    }
+
+    /// The raw records from `from` up to `to`, of `target` or of every
+    /// server, oldest first.
+    pub fn records(&self, target: Option<&str>, from: u64, to: u64) -> Result<Vec<Record>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM records WHERE (?1 IS NULL OR target = ?1) AND timestamp BETWEEN ?2 AND ?3
+            ORDER BY timestamp",
+        )?;
+
+        let records = stmt
+            .query_map(params![target, clamp(from), clamp(to)], record)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(records)
+    }
+
+    /// The latest raw record of `target`, if any.
+    pub fn latest(&self, target: &str) -> Result<Option<Record>, Error> {
+        let conn = self.conn.lock().unwrap();
+
+        Ok(conn
+            .query_row(
+                "SELECT * FROM records WHERE target = ?1 ORDER BY timestamp DESC LIMIT 1",
+                [target],
+                record,
+            )
+            .optional()?)
+    }
+
+    /// Write the raw records from `from` up to `to` as CSV, with a header.
+    pub fn export_csv(
+        &self,
+        target: Option<&str>,
+        from: u64,
+        to: u64,
+        mut writer: impl Write,
+    ) -> Result<(), Error> {
+        writeln!(
+            writer,
+            "timestamp,target,up,latency_ms,players_online,players_max,version,motd_hash"
+        )?;
+
+        for record in self.records(target, from, to)? {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                record.timestamp,
+                csv_field(&record.target),
+                record.up,
+                record.latency.map_or(String::new(), |latency| format!(
+                    "{:.3}",
+                    latency.as_secs_f64() * 1000.0
+                )),
+                record
+                    .players_online
+                    .map_or(String::new(), |n| n.to_string()),
+                record.players_max.map_or(String::new(), |n| n.to_string()),
+                csv_field(record.version.as_deref().unwrap_or("")),
+                record.motd_hash.as_deref().unwrap_or(""),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the raw records from `from` up to `to` as JSON, one per line.
+    pub fn export_ndjson(
+        &self,
+        target: Option<&str>,
+        from: u64,
+        to: u64,
+        mut writer: impl Write,
+    ) -> Result<(), Error> {
+        for record in self.records(target, from, to)? {
+            serde_json::to_writer(&mut writer, &record)?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for History {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("History")
+            .field("retention", &self.retention)
+            .finish()
+    }
+}
+
+/// The query selecting the buckets of a resolution.
+fn source(resolution: Resolution) -> String {
+    match resolution.seconds() {
+        None => RAW_BUCKETS.to_string(),
+        Some(seconds) => format!("SELECT * FROM buckets WHERE resolution = {}", seconds),
+    }
+}
+
+fn record(row: &Row<'_>) -> rusqlite::Result<Record> {
+    Ok(Record {
+        timestamp: row.get::<_, i64>("timestamp")? as u64,
+        target: row.get("target")?,
+        up: row.get("up")?,
+        latency: row
+            .get::<_, Option<i64>>("latency_us")?
+            .map(|us| Duration::from_micros(us as u64)),
+        players_online: row.get("players_online")?,
+        players_max: row.get("players_max")?,
+        version: row.get("version")?,
+        motd_hash: row.get("motd_hash")?,
+    })
+}
+
+fn clamp(timestamp: u64) -> i64 {
+    timestamp.min(i64::MAX as u64) as i64
+}
+
+fn mean(sum: Option<i64>, count: Option<i64>) -> Option<f64> {
+    match (sum, count) {
+        (Some(sum), Some(count)) if count > 0 => Some(sum as f64 / count as f64),
+        _ => None,
+    }
+}
+
+fn micros(us: f64) -> Duration {
+    Duration::from_secs_f64(us.max(0.0) / 1_000_000.0)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
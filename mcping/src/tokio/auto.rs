@@ -0,0 +1,69 @@
+//! Pinging a server without knowing which edition it is running.
+
+use async_trait::async_trait;
+use std::{future::Future, time::Duration};
+use tokio::sync::mpsc;
+
+use crate::{
+    auto::{Answer, Answers},
+    tokio::AsyncPingable,
+    Auto, AutoResponse, BedrockResponse, Error, JavaResponse,
+};
+
+#[async_trait]
+impl AsyncPingable for Auto {
+    type Response = AutoResponse;
+
+    async fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let java = self.java();
+        let bedrock = self.bedrock();
+
+        race(java.ping(), bedrock.ping(), self.grace_period).await
+    }
+}
+
+/// Run both pings as their own task, returning once either both have answered
+/// or the grace period after the first success has passed.
+pub(crate) async fn race<J, B>(
+    java: J,
+    bedrock: B,
+    grace_period: Duration,
+) -> Result<(Duration, AutoResponse), Error>
+where
+    J: Future<Output = Result<(Duration, JavaResponse), Error>> + Send + 'static,
+    B: Future<Output = Result<(Duration, BedrockResponse), Error>> + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::channel(2);
+
+    let java_tx = tx.clone();
+    tokio::spawn(async move {
+        let _ = java_tx.send(Answer::Java(java.await)).await;
+    });
+
+    tokio::spawn(async move {
+        let _ = tx.send(Answer::Bedrock(bedrock.await)).await;
+    });
+
+    let mut answers = Answers::default();
+
+    // Wait for the first answer, and if it was a success give the other
+    // edition a chance to answer as well.
+    if let Some(answer) = rx.recv().await {
+        answers.push(answer);
+    }
+
+    let second = if answers.any_success() {
+        tokio::time::timeout(grace_period, rx.recv())
+            .await
+            .ok()
+            .flatten()
+    } else {
+        rx.recv().await
+    };
+
+    if let Some(answer) = second {
+        answers.push(answer);
+    }
+
+    answers.finish()
+}
@@ -0,0 +1,169 @@
+//! Pinging many servers at once.
+
+use async_trait::async_trait;
+use futures_core::Stream;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::{
+    batch::{BatchOptions, BatchResult, Target},
+    tokio::AsyncPingable,
+    Error, ServerStatus,
+};
+
+#[async_trait]
+impl AsyncPingable for Target {
+    type Response = ServerStatus;
+
+    async fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let status = match self {
+            Target::Java(java) => super::get_status_any(java).await?,
+            Target::Bedrock(bedrock) => super::get_status_any(bedrock).await?,
+            Target::Auto(auto) => super::get_status_any(auto).await?,
+        };
+
+        Ok((status.latency, status))
+    }
+}
+
+/// Ping every target concurrently, yielding results as they complete.
+///
+/// No more than `options.concurrency` pings are in flight at once, and no more
+/// than `options.per_host` of them to the same host.
+///
+/// This must be called from within a tokio runtime, as every ping is spawned as
+/// its own task.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async {
+/// use futures::StreamExt;
+/// use mcping::{BatchOptions, Bedrock, Java, Target};
+///
+/// let targets: Vec<Target> = vec![
+///     Java {
+///         server_address: "mc.hypixel.net".into(),
+///         ..Default::default()
+///     }
+///     .into(),
+///     Bedrock {
+///         server_address: "play.nethergames.org".into(),
+///         ..Default::default()
+///     }
+///     .into(),
+/// ];
+///
+/// let mut results = mcping::tokio::get_status_batch(targets, BatchOptions::default());
+///
+/// while let Some(result) = results.next().await {
+///     match result.result {
+///         Ok(status) => println!("{}: {}ms", result.target.server_address(), status.latency.as_millis()),
+///         Err(e) => println!("{}: {}", result.target.server_address(), e),
+///     }
+/// }
+/// # };
+/// ```
+///
+/// Every Bedrock ping binds its own UDP port, so many can run at once:
+///
+/// ```
+/// use futures::StreamExt;
+/// use mcping::{BatchOptions, Bedrock};
+/// use std::{net::UdpSocket, thread, time::Duration};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let server = UdpSocket::bind("127.0.0.1:0")?;
+/// # let server_address = server.local_addr()?.to_string();
+/// # thread::spawn(move || {
+/// #     let mut buf = [0; 64];
+/// #     while let Ok((_, client)) = server.recv_from(&mut buf) {
+/// #         // Answer slowly, so that the pings overlap.
+/// #         thread::sleep(Duration::from_millis(50));
+/// #         let payload = b"MCPE;A server;390;1.14.60;0;10;1;World;Survival;1;19132;19133;";
+/// #         let mut pong = vec![0x1c];
+/// #         pong.extend_from_slice(&[0; 16]);
+/// #         pong.extend_from_slice(&buf[9..25]);
+/// #         pong.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+/// #         pong.extend_from_slice(payload);
+/// #         server.send_to(&pong, client).unwrap();
+/// #     }
+/// # });
+/// let targets = (0..6).map(|_| Bedrock {
+///     server_address: server_address.clone(),
+///     timeout: Some(Duration::from_secs(5)),
+///     tries: 1,
+///     ..Default::default()
+/// });
+/// let options = BatchOptions {
+///     per_host: 6,
+///     ..Default::default()
+/// };
+///
+/// let mut results = mcping::tokio::get_status_batch(targets, options);
+///
+/// while let Some(result) = results.next().await {
+///     assert_eq!(result.result?.motd.text(), "A server");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_status_batch<I>(targets: I, options: BatchOptions) -> BatchStream
+where
+    I: IntoIterator,
+    I::Item: Into<Target>,
+{
+    let global = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut hosts: HashMap<String, Arc<Semaphore>> = HashMap::new();
+
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    for (index, target) in targets.into_iter().map(Into::into).enumerate() {
+        let host = Arc::clone(
+            hosts
+                .entry(target.host())
+                .or_insert_with(|| Arc::new(Semaphore::new(options.per_host.max(1)))),
+        );
+        let global = Arc::clone(&global);
+        let tx = tx.clone();
+
+        tokio::spawn(async move {
+            // Waiting on the host first means a global slot is never held by a
+            // ping that can't start yet.
+            let _host = host.acquire_owned().await;
+            let _global = global.acquire_owned().await;
+
+            let result = target.clone().ping().await.map(|(_, status)| status);
+
+            let _ = tx.send(BatchResult {
+                index,
+                target,
+                result,
+            });
+        });
+    }
+
+    BatchStream { rx }
+}
+
+/// A stream of the results of [`get_status_batch`], in the order the pings
+/// complete.
+#[derive(Debug)]
+pub struct BatchStream {
+    rx: mpsc::UnboundedReceiver<BatchResult>,
+}
+
+impl Stream for BatchStream {
+    type Item = BatchResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
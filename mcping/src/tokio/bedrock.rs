@@ -11,22 +11,48 @@ use tokio::{
     io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
     net::UdpSocket,
 };
-use trust_dns_resolver::{config::*, TokioAsyncResolver};
 
 use crate::{
     bedrock::{Packet, DEFAULT_PORT, OFFLINE_MESSAGE_DATA_ID},
-    tokio::AsyncPingable,
-    Bedrock, BedrockResponse, Error,
+    tokio::{
+        connect::{self, Net},
+        AsyncPingable,
+    },
+    Bedrock, BedrockResponse, Error, PingMetadata,
 };
 
 #[async_trait]
 impl AsyncPingable for Bedrock {
     type Response = BedrockResponse;
 
-    async fn ping(self) -> Result<(u64, Self::Response), Error> {
-        let mut connection =
-            Connection::new(&self.server_address, &self.socket_addresses, self.timeout).await?;
+    async fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let resolver = connect::resolver()?;
 
+        self.ping_with_async(&Net {
+            resolver: &resolver,
+            bind_address: None,
+            proxy: None,
+            address_policy: self.address_policy.as_ref(),
+            rate_limiter: self.rate_limiter.as_ref(),
+        })
+        .await
+    }
+}
+
+impl Bedrock {
+    pub(crate) async fn ping_with_async(
+        &self,
+        net: &Net<'_>,
+    ) -> Result<(Duration, BedrockResponse), Error> {
+        let mut connection = Connection::new(
+            net,
+            &self.server_address,
+            &self.socket_addresses,
+            self.timeout,
+        )
+        .await?;
+
+        let sent = Instant::now();
         for _ in 0..self.tries {
             connection.send(Packet::UnconnectedPing).await?;
 
@@ -37,22 +63,18 @@ impl AsyncPingable for Bedrock {
 
         let before = Instant::now();
         if let Packet::UnconnectedPong { payload, .. } = connection.read().await? {
-            let latency = (Instant::now() - before).as_millis() as u64;
+            let latency = before.elapsed();
 
             // Attempt to extract useful information from the payload.
-            if let Some(response) = BedrockResponse::extract(&payload) {
+            if let Some(mut response) = BedrockResponse::extract(&payload) {
+                connection.metadata.status = sent.elapsed();
+                response.metadata = Some(connection.metadata);
                 Ok((latency, response))
             } else {
-                Err(Error::IoError(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Invalid Payload",
-                )))
+                Err(Error::IoError(io::Error::other("Invalid Payload")))
             }
         } else {
-            Err(Error::IoError(io::Error::new(
-                io::ErrorKind::Other,
-                "Invalid Packet Response",
-            )))
+            Err(Error::IoError(io::Error::other("Invalid Packet Response")))
         }
     }
 }
@@ -67,8 +89,7 @@ trait AsyncReadBedrockExt: AsyncRead + AsyncReadExt + Unpin {
         let len = self.read_u16().await?;
         let mut buf = vec![0; len as usize];
         self.read_exact(&mut buf).await?;
-        String::from_utf8(buf)
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Invalid UTF-8 String."))
+        String::from_utf8(buf).map_err(|_| io::Error::other("Invalid UTF-8 String."))
     }
 }
 
@@ -77,45 +98,34 @@ impl<T: AsyncRead + AsyncReadExt + Unpin> AsyncReadBedrockExt for T {}
 /// Udp Socket Connection to a Raknet Bedrock Server.
 struct Connection {
     socket: UdpSocket,
+    timeout: Option<Duration>,
+    metadata: PingMetadata,
 }
 
 impl Connection {
     async fn new(
+        net: &Net<'_>,
         address: &str,
         socket_addresses: &[SocketAddr],
         timeout: Option<Duration>,
     ) -> Result<Self, Error> {
-        let mut parts = address.split(':');
-
-        let host = parts.next().ok_or(Error::InvalidAddress)?.to_string();
-
-        let port = if let Some(port) = parts.next() {
-            port.parse::<u16>().map_err(|_| Error::InvalidAddress)?
-        } else {
-            DEFAULT_PORT
-        };
+        let (host, port) = crate::connect::parse_address(address, DEFAULT_PORT)?;
+        let resolved = connect::lookup_bedrock(net.resolver, &host, port).await?;
 
-        // Do a hostname lookup
-        let resolver =
-            TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default()).unwrap();
+        net.check_address(resolved.addr)?;
 
-        let ip = resolver
-            .lookup_ip(host.as_str())
-            .await
-            .ok()
-            .and_then(|ips| ips.iter().next())
-            .ok_or(Error::DnsLookupFailed)?;
+        if let Some(limiter) = net.rate_limiter {
+            limiter.wait_async(&host, resolved.addr.ip()).await?;
+        }
 
+        let start = Instant::now();
         let socket = UdpSocket::bind(socket_addresses).await?;
-        socket.connect((ip, port)).await?;
-
-        let socket = socket.into_std()?;
-
-        socket.set_read_timeout(timeout)?;
-        socket.set_write_timeout(timeout)?;
+        socket.connect(resolved.addr).await?;
 
         Ok(Self {
-            socket: UdpSocket::from_std(socket)?,
+            socket,
+            timeout,
+            metadata: resolved.metadata(start.elapsed()),
         })
     }
 
@@ -129,12 +139,7 @@ impl Connection {
 
                 self.socket.send(&buf).await?;
             }
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Invalid C -> S Packet",
-                ))
-            }
+            _ => return Err(io::Error::other("Invalid C -> S Packet")),
         }
 
         Ok(())
@@ -142,7 +147,13 @@ impl Connection {
 
     async fn read(&mut self) -> Result<Packet, io::Error> {
         let mut buf = vec![0; 1024];
-        self.socket.recv(&mut buf).await?;
+
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.socket.recv(&mut buf))
+                .await
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??,
+            None => self.socket.recv(&mut buf).await?,
+        };
 
         let mut buf = Cursor::new(&buf);
 
@@ -156,8 +167,7 @@ impl Connection {
                 buf.read_exact(&mut tmp).await?;
 
                 if tmp != OFFLINE_MESSAGE_DATA_ID {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
+                    return Err(io::Error::other(
                         "incorrect offline message data ID received",
                     ));
                 }
@@ -170,10 +180,7 @@ impl Connection {
                     payload,
                 })
             }
-            _ => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Invalid S -> C Packet",
-            )),
+            _ => Err(io::Error::other("Invalid S -> C Packet")),
         }
     }
 }
@@ -0,0 +1,89 @@
+//! Caching responses so that servers pinged often are pinged less.
+
+use async_trait::async_trait;
+use std::{future::Future, sync::Arc, time::Duration};
+
+use crate::{
+    cache::{erase, restore, Flight, Lookup, Outcome},
+    tokio::AsyncPingable,
+    CacheKey, Cached, Error, ResponseCache,
+};
+
+#[async_trait]
+impl<P> AsyncPingable for Cached<P>
+where
+    P: AsyncPingable + CacheKey + Send + 'static,
+    P::Response: Clone + Send + Sync + 'static,
+{
+    type Response = P::Response;
+
+    async fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let key = self.pingable.cache_key()?;
+
+        let pingable = self.pingable;
+
+        self.cache
+            .get_or_ping_async(key, async move {
+                pingable.ping().await.map(|value| (value, ()))
+            })
+            .await
+            .map(|(value, ())| value)
+    }
+}
+
+impl ResponseCache {
+    /// Return the cached outcome for `key`, or await `ping` and cache its
+    /// outcome.
+    ///
+    /// See [`ResponseCache::get_or_ping`].
+    pub(crate) async fn get_or_ping_async<R, A, Fut>(
+        &self,
+        key: String,
+        ping: Fut,
+    ) -> Result<((Duration, R), A), Error>
+    where
+        R: Clone + Send + Sync + 'static,
+        A: Default,
+        Fut: Future<Output = Result<((Duration, R), A), Error>> + Send + 'static,
+    {
+        let outcome = match self.lookup::<R>(key) {
+            Lookup::Fresh(outcome) => outcome,
+            Lookup::Stale(outcome, refresh) => {
+                if let Some(pending) = refresh {
+                    tokio::spawn(async move {
+                        pending
+                            .complete(ping.await.map(|(value, _)| erase(value)).map_err(Arc::new))
+                    });
+                }
+                outcome
+            }
+            Lookup::Wait(flight) => wait(&flight).await,
+            Lookup::Miss(pending) => match ping.await {
+                Ok((value, own)) => {
+                    pending.complete(Ok(erase(value.clone())));
+                    return Ok((value, own));
+                }
+                Err(err) => {
+                    pending.complete(Err(Arc::new(err.duplicate())));
+                    return Err(err);
+                }
+            },
+        };
+
+        restore(outcome).map(|value| (value, A::default()))
+    }
+}
+
+/// Wait for another caller's ping to finish.
+async fn wait(flight: &Flight) -> Outcome {
+    let mut rx = flight.watch.1.clone();
+
+    loop {
+        if let Some(outcome) = flight.outcome() {
+            return outcome;
+        }
+
+        // The flight owns the sender, so this can't fail while it is alive.
+        let _ = rx.changed().await;
+    }
+}
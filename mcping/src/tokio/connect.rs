@@ -0,0 +1,199 @@
+//! DNS resolution and connection setup shared by both editions.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpSocket, TcpStream},
+};
+use trust_dns_resolver::{config::*, TokioAsyncResolver};
+
+use crate::{
+    connect::{socks5, Resolved},
+    AddressPolicy, Error, Proxy, RateLimiter,
+};
+
+/// How connections are made, beyond the configuration of a single ping.
+pub(crate) struct Net<'a> {
+    pub(crate) resolver: &'a TokioAsyncResolver,
+    /// The local address to bind TCP connections to.
+    pub(crate) bind_address: Option<IpAddr>,
+    pub(crate) proxy: Option<&'a Proxy>,
+    pub(crate) address_policy: Option<&'a AddressPolicy>,
+    pub(crate) rate_limiter: Option<&'a RateLimiter>,
+}
+
+impl Net<'_> {
+    /// Make sure the address policy allows connecting to `addr`.
+    pub(crate) fn check_address(&self, addr: SocketAddr) -> Result<(), Error> {
+        match self.address_policy {
+            Some(policy) => policy
+                .check(addr)
+                .map_err(|reason| Error::AddressBlocked { addr, reason }),
+            None => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn resolver() -> Result<TokioAsyncResolver, Error> {
+    TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+        .map_err(|_| Error::DnsLookupFailed)
+}
+
+/// Find the address of a Java server.
+///
+/// See [`crate::connect::lookup_java`] for how the SRV record is used.
+pub(crate) async fn lookup_java(
+    resolver: &TokioAsyncResolver,
+    host: &str,
+    port: u16,
+) -> Result<Resolved, Error> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(Resolved::ip(ip, port));
+    }
+
+    let start = Instant::now();
+    let record = match resolver
+        .srv_lookup(format!("_minecraft._tcp.{}.", host))
+        .await
+    {
+        Ok(lookup) => lookup.into_iter().next(),
+        Err(_) => None,
+    };
+    let srv_lookup = Some(start.elapsed());
+
+    let start = Instant::now();
+    let srv = match record {
+        Some(record) => lookup_ip(resolver, &record.target().to_string())
+            .await
+            .map(|ip| SocketAddr::new(ip, record.port())),
+        None => None,
+    };
+
+    let (addr, srv_used) = match srv {
+        Some(addr) => (addr, true),
+        None => (
+            lookup_ip(resolver, host)
+                .await
+                .map(|ip| SocketAddr::new(ip, port))
+                .ok_or(Error::DnsLookupFailed)?,
+            false,
+        ),
+    };
+
+    Ok(Resolved {
+        addr,
+        srv_used,
+        srv_lookup,
+        dns_lookup: Some(start.elapsed()),
+    })
+}
+
+/// Find the address of a Bedrock server, which doesn't use SRV records.
+pub(crate) async fn lookup_bedrock(
+    resolver: &TokioAsyncResolver,
+    host: &str,
+    port: u16,
+) -> Result<Resolved, Error> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(Resolved::ip(ip, port));
+    }
+
+    let start = Instant::now();
+    let ip = lookup_ip(resolver, host)
+        .await
+        .ok_or(Error::DnsLookupFailed)?;
+
+    Ok(Resolved {
+        addr: SocketAddr::new(ip, port),
+        srv_used: false,
+        srv_lookup: None,
+        dns_lookup: Some(start.elapsed()),
+    })
+}
+
+async fn lookup_ip(resolver: &TokioAsyncResolver, host: &str) -> Option<IpAddr> {
+    resolver.lookup_ip(host).await.ok()?.into_iter().next()
+}
+
+/// Open a TCP connection to `addr`, going through the proxy if one is set.
+pub(crate) async fn connect_tcp(
+    net: &Net<'_>,
+    addr: SocketAddr,
+    timeout: Option<Duration>,
+) -> Result<TcpStream, Error> {
+    let target = net
+        .proxy
+        .map_or(addr, |Proxy::Socks5 { address, .. }| *address);
+
+    let socket = match target {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+
+    if let Some(ip) = net.bind_address {
+        socket.bind(SocketAddr::new(ip, 0))?;
+    }
+
+    let connect = async {
+        let mut stream = socket.connect(target).await?;
+        stream.set_nodelay(true)?;
+
+        if let Some(Proxy::Socks5 { credentials, .. }) = net.proxy {
+            socks5_connect(&mut stream, addr, credentials.as_ref()).await?;
+        }
+
+        Ok(stream)
+    };
+
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connect)
+            .await
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))?,
+        None => connect.await,
+    }
+}
+
+/// Perform the SOCKS5 handshake asking the proxy to connect to `addr`.
+///
+/// See https://datatracker.ietf.org/doc/html/rfc1928
+async fn socks5_connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    addr: SocketAddr,
+    credentials: Option<&(String, String)>,
+) -> Result<(), Error> {
+    stream.write_all(&socks5::greeting(credentials)).await?;
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+
+    match socks5::method(reply)? {
+        socks5::Method::None => {}
+        socks5::Method::Password => {
+            stream.write_all(&socks5::password(credentials)?).await?;
+            stream.read_exact(&mut reply).await?;
+            socks5::check_password_reply(reply)?;
+        }
+    }
+
+    stream.write_all(&socks5::connect_request(addr)).await?;
+
+    let mut header = [0; 4];
+    stream.read_exact(&mut header).await?;
+    socks5::check_connect_reply(header)?;
+
+    // Skip the address the proxy bound to.
+    let len = match header[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        _ => return Err(socks5::error("invalid address type")),
+    };
+    let mut bound = vec![0; len];
+    stream.read_exact(&mut bound).await?;
+    stream.read_u16().await?;
+
+    Ok(())
+}
@@ -4,24 +4,74 @@
 use async_trait::async_trait;
 use std::{
     io::{self, Cursor},
-    net::{IpAddr, SocketAddr},
     time::{Duration, Instant},
 };
 use tokio::{
     io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::TcpStream,
 };
-use trust_dns_resolver::{config::*, TokioAsyncResolver};
 
-use crate::{java::Packet, tokio::AsyncPingable, Error, Java, JavaResponse};
+use crate::{
+    java::{pong_lost, Packet, DEFAULT_PORT, PONG_TIMEOUT},
+    latency::no_pong,
+    tokio::{
+        connect::{self, Net},
+        AsyncPingable,
+    },
+    Error, Java, JavaResponse, LatencySource, PingMetadata,
+};
 
 #[async_trait]
 impl AsyncPingable for Java {
     type Response = JavaResponse;
 
-    async fn ping(self) -> Result<(u64, Self::Response), crate::Error> {
-        let mut conn = Connection::new(&self.server_address, self.timeout).await?;
+    async fn ping(self) -> Result<(Duration, Self::Response), crate::Error> {
+        let resolver = connect::resolver()?;
+
+        self.ping_with_async(&Net {
+            resolver: &resolver,
+            bind_address: None,
+            proxy: None,
+            address_policy: self.address_policy.as_ref(),
+            rate_limiter: self.rate_limiter.as_ref(),
+        })
+        .await
+    }
+}
 
+impl Java {
+    pub(crate) async fn ping_with_async(
+        &self,
+        net: &Net<'_>,
+    ) -> Result<(Duration, JavaResponse), Error> {
+        let mut conn = Connection::new(net, &self.server_address, self.timeout).await?;
+        let mut response = self.status_async(&mut conn).await?;
+
+        let pong_timeout = self.timeout.unwrap_or(PONG_TIMEOUT);
+        let pong = tokio::time::timeout(pong_timeout, conn.ping(rand::random()))
+            .await
+            .unwrap_or_else(|_| Err(no_pong()));
+
+        let latency = match pong {
+            Ok(pong) => {
+                conn.metadata.pong = Some(pong);
+                pong
+            }
+            // Some proxies and server jars close the connection instead of
+            // answering the ping, which shouldn't lose the status.
+            Err(e) if pong_lost(&e) => {
+                conn.metadata.latency_source = LatencySource::Status;
+                conn.metadata.status
+            }
+            Err(e) => return Err(e),
+        };
+
+        response.metadata = Some(conn.metadata);
+        Ok((latency, response))
+    }
+
+    /// Perform the handshake and read the status response.
+    pub(crate) async fn status_async(&self, conn: &mut Connection) -> Result<JavaResponse, Error> {
         // Handshake
         conn.send_packet(Packet::Handshake {
             version: 47,
@@ -32,26 +82,16 @@ impl AsyncPingable for Java {
         .await?;
 
         // Request
+        let start = Instant::now();
         conn.send_packet(Packet::Request {}).await?;
 
-        let resp = match conn.read_packet().await? {
-            Packet::Response { response } => serde_json::from_str(&response)?,
+        let response = match conn.read_packet().await? {
+            Packet::Response { response } => self.parse_response(response)?,
             _ => return Err(Error::InvalidPacket),
         };
 
-        // Ping Request
-        let r = rand::random();
-        conn.send_packet(Packet::Ping { payload: r }).await?;
-
-        let before = Instant::now();
-        let ping = match conn.read_packet().await? {
-            Packet::Pong { payload } if payload == r => {
-                (Instant::now() - before).as_millis() as u64
-            }
-            _ => return Err(Error::InvalidPacket),
-        };
-
-        Ok((ping, resp))
+        conn.metadata.status = start.elapsed();
+        Ok(response)
     }
 }
 
@@ -66,12 +106,12 @@ trait AsyncReadJavaExt: AsyncRead + AsyncReadExt + Unpin {
                 return Ok(res);
             }
         }
-        Err(io::Error::new(io::ErrorKind::Other, "VarInt too big!"))
+        Err(io::Error::other("VarInt too big!"))
     }
 
     async fn read_string(&mut self) -> io::Result<String> {
         let len = self.read_varint().await? as usize;
-        let mut buf = vec![0; len as usize];
+        let mut buf = vec![0; len];
         self.read_exact(&mut buf).await?;
         Ok(String::from_utf8(buf).expect("Invalid UTF-8 String."))
     }
@@ -90,7 +130,7 @@ trait AsyncWriteJavaExt: AsyncWrite + AsyncWriteExt + Unpin {
             self.write_u8((val & 0x7F | 0x80) as u8).await?;
             val >>= 7;
         }
-        Err(io::Error::new(io::ErrorKind::Other, "VarInt too big!"))
+        Err(io::Error::other("VarInt too big!"))
     }
 
     async fn write_string(&mut self, s: &str) -> io::Result<()> {
@@ -102,78 +142,53 @@ trait AsyncWriteJavaExt: AsyncWrite + AsyncWriteExt + Unpin {
 
 impl<T> AsyncWriteJavaExt for T where T: AsyncWrite + AsyncWriteExt + Unpin {}
 
-struct Connection {
+pub(crate) struct Connection {
     stream: TcpStream,
     host: String,
     port: u16,
+    pub(crate) metadata: PingMetadata,
 }
 
 impl Connection {
-    async fn new(address: &str, timeout: Option<Duration>) -> Result<Self, Error> {
+    pub(crate) async fn new(
+        net: &Net<'_>,
+        address: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
         // Split the address up into it's parts, saving the host and port for later and converting the
         // potential domain into an ip
-        let mut parts = address.split(':');
+        let (host, port) = crate::connect::parse_address(address, DEFAULT_PORT)?;
+        let resolved = connect::lookup_java(net.resolver, &host, port).await?;
 
-        let host = parts.next().ok_or(Error::InvalidAddress)?.to_string();
+        net.check_address(resolved.addr)?;
 
-        // If a port exists we want to try and parse it and if not we will
-        // default to 25565 (Minecraft)
-        let port = if let Some(port) = parts.next() {
-            port.parse::<u16>().map_err(|_| Error::InvalidAddress)?
-        } else {
-            25565
-        };
-
-        // Attempt to lookup the ip of the server from an srv record, falling back on the ip from a host
-        let resolver =
-            TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default()).unwrap();
-
-        // Determine what host to lookup by doing the following:
-        // - Lookup the SRV record for the domain, if it exists perform a lookup of the ip from the target
-        //   and grab the port pointed at by the record.
-        //
-        //   Note: trust_dns_resolver should do a recursive lookup for an ip but it doesn't seem to at
-        //   the moment.
-        //
-        // - If the above failed in any way fall back to the normal ip lookup from the host provided
-        //   and use the provided port.
-
-        let srv_lookup = resolver
-            .srv_lookup(format!("_minecraft._tcp.{}.", &host))
-            .await
-            .ok();
-        let ip: IpAddr = match srv_lookup {
-            Some(lookup) => match lookup.into_iter().next() {
-                Some(record) => resolver
-                    .lookup_ip(record.target().to_string())
-                    .await
-                    .ok()
-                    .and_then(|lookup_ip| lookup_ip.into_iter().next()),
-                None => None,
-            },
-            None => resolver
-                .lookup_ip(host.clone())
-                .await
-                .ok()
-                .and_then(|lookup_ip| lookup_ip.into_iter().next()),
+        if let Some(limiter) = net.rate_limiter {
+            limiter.wait_async(&host, resolved.addr.ip()).await?;
         }
-        .ok_or(Error::DnsLookupFailed)?;
 
-        let socket_addr = SocketAddr::new(ip, port);
-
-        let stream = TcpStream::connect(&socket_addr).await?.into_std()?;
-
-        stream.set_read_timeout(timeout)?;
-        stream.set_write_timeout(timeout)?;
+        let start = Instant::now();
+        let stream = connect::connect_tcp(net, resolved.addr, timeout).await?;
 
         Ok(Self {
-            stream: TcpStream::from_std(stream)?,
+            stream,
             host,
-            port,
+            port: resolved.addr.port(),
+            metadata: resolved.metadata(start.elapsed()),
         })
     }
 
-    async fn send_packet(&mut self, p: Packet) -> Result<(), Error> {
+    /// Send a ping, returning how long the pong took to arrive.
+    pub(crate) async fn ping(&mut self, payload: u64) -> Result<Duration, Error> {
+        let before = Instant::now();
+        self.send_packet(Packet::Ping { payload }).await?;
+
+        match self.read_packet().await? {
+            Packet::Pong { payload: pong } if pong == payload => Ok(before.elapsed()),
+            _ => Err(Error::InvalidPacket),
+        }
+    }
+
+    pub(crate) async fn send_packet(&mut self, p: Packet) -> Result<(), Error> {
         let mut buf = Vec::new();
         match p {
             Packet::Handshake {
@@ -197,12 +212,17 @@ impl Connection {
             }
             _ => return Err(Error::InvalidPacket),
         }
-        self.stream.write_varint(buf.len() as i32).await?;
-        self.stream.write_all(&buf).await?;
+
+        // Write the whole frame at once, so the length and body aren't sent
+        // as separate segments.
+        let mut frame = Vec::with_capacity(buf.len() + 5);
+        frame.write_varint(buf.len() as i32).await?;
+        frame.extend_from_slice(&buf);
+        self.stream.write_all(&frame).await?;
         Ok(())
     }
 
-    async fn read_packet(&mut self) -> Result<Packet, Error> {
+    pub(crate) async fn read_packet(&mut self) -> Result<Packet, Error> {
         let len = self.stream.read_varint().await?;
         let mut buf = vec![0; len as usize];
         self.stream.read_exact(&mut buf).await?;
@@ -0,0 +1,74 @@
+//! Measuring latency with several pings over one Java connection.
+
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+
+use crate::{
+    java::Packet,
+    latency::{no_pong, offset},
+    tokio::{
+        connect::{self, Net},
+        java::Connection,
+        AsyncPingable,
+    },
+    Error, LatencyProbe, LatencyStats, ProbeResponse,
+};
+
+#[async_trait]
+impl AsyncPingable for LatencyProbe {
+    type Response = ProbeResponse;
+
+    async fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let resolver = connect::resolver()?;
+        let net = Net {
+            resolver: &resolver,
+            bind_address: None,
+            proxy: None,
+            address_policy: self.java.address_policy.as_ref(),
+            rate_limiter: self.java.rate_limiter.as_ref(),
+        };
+
+        let java = &self.java;
+        let mut conn = Connection::new(&net, &java.server_address, java.timeout).await?;
+        let mut response = java.status_async(&mut conn).await?;
+
+        let base: u64 = rand::random();
+        let start = tokio::time::Instant::now();
+        let mut samples = Vec::with_capacity(self.samples());
+
+        for i in 0..self.samples() {
+            match offset(self.interval, i).and_then(|offset| start.checked_add(offset)) {
+                Some(at) => tokio::time::sleep_until(at).await,
+                None => tokio::time::sleep(self.interval).await,
+            }
+
+            let payload = base.wrapping_add(i as u64);
+            match tokio::time::timeout(self.pong_timeout, sample(&mut conn, payload)).await {
+                Ok(Ok(rtt)) => samples.push(rtt),
+                // The connection is unusable, even after a timeout cancelled the
+                // read halfway through a packet, so every remaining ping is lost.
+                _ => break,
+            }
+        }
+
+        let stats = LatencyStats::new(samples, self.samples()).ok_or_else(no_pong)?;
+
+        conn.metadata.pong = Some(stats.median);
+        response.metadata = Some(conn.metadata);
+        Ok((stats.median, ProbeResponse { response, stats }))
+    }
+}
+
+/// Send a ping and wait for its pong, ignoring pongs to other payloads.
+async fn sample(conn: &mut Connection, payload: u64) -> Result<Duration, Error> {
+    let before = Instant::now();
+    conn.send_packet(Packet::Ping { payload }).await?;
+
+    loop {
+        match conn.read_packet().await? {
+            Packet::Pong { payload: pong } if pong == payload => return Ok(before.elapsed()),
+            Packet::Pong { .. } => {}
+            _ => return Err(Error::InvalidPacket),
+        }
+    }
+}
@@ -1,9 +1,23 @@
+mod auto;
+mod batch;
 mod bedrock;
-mod java;
+mod cache;
+mod connect;
+pub(crate) mod java;
+mod latency;
+mod monitor;
+mod pinger;
+mod ratelimit;
+mod retry;
+
+pub use batch::{get_status_batch, BatchStream};
+pub use monitor::{monitor, Monitor, MonitorEvent, MonitorEventKind, MonitorOptions, Watch};
+pub use pinger::Pinger;
 
 use async_trait::async_trait;
+use std::time::Duration;
 
-use crate::Error;
+use crate::{Error, ServerStatus};
 
 /// Represents a pingable entity.
 #[async_trait]
@@ -12,13 +26,13 @@ pub trait AsyncPingable {
     type Response;
 
     /// Ping the entity, gathering the latency and response.
-    async fn ping(self) -> Result<(u64, Self::Response), Error>;
+    async fn ping(self) -> Result<(Duration, Self::Response), Error>;
 }
 
 /// Retrieve the status of a given Minecraft server using a `AsyncPingable` configuration.
 ///
 ///
-/// Returns `(latency_ms, response)` where response is a response type of the `Pingable` configuration.
+/// Returns `(latency, response)` where response is a response type of the `Pingable` configuration.
 ///
 /// # Examples
 ///
@@ -31,6 +45,7 @@ pub trait AsyncPingable {
 /// let (latency, response) = mcping::tokio::get_status(mcping::Java {
 ///     server_address: "mc.hypixel.net".into(),
 ///     timeout: None,
+///     ..Default::default()
 /// }).await?;
 /// # Ok::<(), mcping::Error>(())
 /// # };
@@ -51,6 +66,31 @@ pub trait AsyncPingable {
 /// # Ok::<(), mcping::Error>(())
 /// # };
 /// ```
-pub async fn get_status<P: AsyncPingable>(pingable: P) -> Result<(u64, P::Response), Error> {
+pub async fn get_status<P: AsyncPingable>(pingable: P) -> Result<(Duration, P::Response), Error> {
     pingable.ping().await
 }
+
+/// Retrieve the status of a given Minecraft server as a [`ServerStatus`],
+/// regardless of its edition.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async {
+/// let status = mcping::tokio::get_status_any(mcping::Bedrock {
+///     server_address: "play.nethergames.org".into(),
+///     ..Default::default()
+/// })
+/// .await?;
+///
+/// println!("{} ({}): {}", status.version_name, status.edition, status.motd.text());
+/// # Ok::<(), mcping::Error>(())
+/// # };
+/// ```
+pub async fn get_status_any<P>(pingable: P) -> Result<ServerStatus, Error>
+where
+    P: AsyncPingable,
+    ServerStatus: From<(Duration, P::Response)>,
+{
+    pingable.ping().await.map(ServerStatus::from)
+}
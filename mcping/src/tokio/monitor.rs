@@ -0,0 +1,345 @@
+//! Watching servers for changes in their status.
+
+use futures_core::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::mpsc, task::JoinHandle, time::Instant};
+
+use crate::{batch::Target, tokio::AsyncPingable, Chat, Error, Player, ServerStatus};
+
+/// How a [`Monitor`] decides that a server is down.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct MonitorOptions {
+    /// How many pings in a row have to fail before a server is considered
+    /// down, so that a single lost ping doesn't count as an outage.
+    pub failures_before_down: usize,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self {
+            failures_before_down: 3,
+        }
+    }
+}
+
+/// Something that changed about a monitored server.
+#[derive(Debug)]
+pub struct MonitorEvent {
+    /// The position of the target in the monitored targets.
+    pub index: usize,
+    /// The target the event is about.
+    pub target: Target,
+    /// What changed.
+    pub kind: MonitorEventKind,
+}
+
+/// What changed about a monitored server.
+///
+/// Changes are found by comparing each status with the previous one the
+/// server answered with, even if it was down in between.
+#[derive(Debug)]
+pub enum MonitorEventKind {
+    /// The server answered, for the first time or after being down.
+    Up(ServerStatus),
+    /// The server stopped answering, failing with this error last.
+    Down(Error),
+    /// The MOTD changed.
+    MotdChanged { old: Chat, new: Chat },
+    /// The version name or protocol changed.
+    VersionChanged {
+        old_name: String,
+        new_name: String,
+        old_protocol: Option<i64>,
+        new_protocol: Option<i64>,
+    },
+    /// The server icon changed, as Base64-encoded PNG images.
+    FaviconChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// A player appeared in the sample of online players.
+    PlayerAppeared(Player),
+    /// A player is no longer in the sample of online players.
+    PlayerDisappeared(Player),
+}
+
+/// Ping every target on its own interval, yielding events as their statuses
+/// change.
+///
+/// Each target is pinged again `interval` after the previous ping started, or
+/// right after it finished if it took longer than that. Give every target a
+/// timeout, as a ping that never finishes stops its target from being pinged.
+///
+/// This must be called from within a tokio runtime. The pings stop when the
+/// returned [`Monitor`] is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async {
+/// use mcping::{
+///     tokio::{MonitorEventKind, MonitorOptions},
+///     Java, Target,
+/// };
+/// use std::time::Duration;
+///
+/// let hypixel = Java {
+///     server_address: "mc.hypixel.net".into(),
+///     timeout: Some(Duration::from_secs(5)),
+///     ..Default::default()
+/// };
+///
+/// let mut monitor = mcping::tokio::monitor(
+///     vec![(Target::from(hypixel), Duration::from_secs(30))],
+///     MonitorOptions::default(),
+/// );
+///
+/// while let Some(event) = monitor.recv().await {
+///     match event.kind {
+///         MonitorEventKind::Up(_) => println!("{} is up", event.target.server_address()),
+///         MonitorEventKind::Down(e) => println!("{} is down: {}", event.target.server_address(), e),
+///         other => println!("{}: {:?}", event.target.server_address(), other),
+///     }
+/// }
+/// # };
+/// ```
+pub fn monitor<I>(targets: I, options: MonitorOptions) -> Monitor
+where
+    I: IntoIterator<Item = (Target, Duration)>,
+{
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let tasks = targets
+        .into_iter()
+        .enumerate()
+        .map(|(index, (target, interval))| {
+            let tx = tx.clone();
+
+            tokio::spawn(async move {
+                let mut watch = Watch::default();
+                let mut next = Instant::now();
+
+                loop {
+                    tokio::time::sleep_until(next).await;
+                    next = (next + interval).max(Instant::now());
+
+                    let result = target.clone().ping().await.map(|(_, status)| status);
+
+                    for kind in watch.observe(result, &options) {
+                        let event = MonitorEvent {
+                            index,
+                            target: target.clone(),
+                            kind,
+                        };
+
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+
+    Monitor { rx, tasks }
+}
+
+/// A stream of the events of [`monitor`].
+#[derive(Debug)]
+pub struct Monitor {
+    rx: mpsc::UnboundedReceiver<MonitorEvent>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Monitor {
+    /// Wait for the next event.
+    pub async fn recv(&mut self) -> Option<MonitorEvent> {
+        self.rx.recv().await
+    }
+}
+
+impl Stream for Monitor {
+    type Item = MonitorEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// What is known about a monitored server, turning each of its ping results
+/// into the events [`monitor`] yields.
+///
+/// This can watch a server that is pinged some other way.
+///
+/// # Examples
+///
+/// ```
+/// use mcping::{
+///     tokio::{MonitorEventKind, MonitorOptions, Watch},
+///     Chat, Edition, Error, Player, ServerStatus,
+/// };
+/// use std::time::Duration;
+///
+/// let player = |name: &str| Player {
+///     name: name.into(),
+///     id: name.into(),
+/// };
+/// let old = ServerStatus {
+///     edition: Edition::Java,
+///     version_name: "1.20.4".into(),
+///     protocol: Some(765),
+///     players_online: Some(1),
+///     players_max: Some(20),
+///     motd: Chat::String("A server".into()),
+///     icon: None,
+///     sample: Some(vec![player("alice")]),
+///     latency: Duration::from_millis(40),
+///     metadata: None,
+/// };
+/// let new = ServerStatus {
+///     version_name: "1.21".into(),
+///     protocol: Some(767),
+///     motd: Chat::String("An updated server".into()),
+///     sample: Some(vec![player("bob")]),
+///     ..old.clone()
+/// };
+///
+/// let options = MonitorOptions {
+///     failures_before_down: 2,
+/// };
+/// let mut watch = Watch::default();
+/// let mut observe = |result: Result<&ServerStatus, Error>| -> Vec<String> {
+///     watch
+///         .observe(result.cloned(), &options)
+///         .into_iter()
+///         .map(|kind| match kind {
+///             MonitorEventKind::Up(_) => "up".to_string(),
+///             MonitorEventKind::Down(_) => "down".to_string(),
+///             MonitorEventKind::MotdChanged { .. } => "motd".to_string(),
+///             MonitorEventKind::VersionChanged { .. } => "version".to_string(),
+///             MonitorEventKind::FaviconChanged { .. } => "favicon".to_string(),
+///             MonitorEventKind::PlayerAppeared(p) => format!("+{}", p.name),
+///             MonitorEventKind::PlayerDisappeared(p) => format!("-{}", p.name),
+///         })
+///         .collect()
+/// };
+///
+/// assert_eq!(observe(Ok(&old)), ["up"]);
+/// // A single lost ping is within the debounce.
+/// assert!(observe(Err(Error::DnsLookupFailed)).is_empty());
+/// assert!(observe(Ok(&old)).is_empty());
+/// assert_eq!(observe(Ok(&new)), ["motd", "version", "+bob", "-alice"]);
+///
+/// assert!(observe(Err(Error::DnsLookupFailed)).is_empty());
+/// assert_eq!(observe(Err(Error::DnsLookupFailed)), ["down"]);
+/// assert!(observe(Err(Error::DnsLookupFailed)).is_empty());
+/// // Changes are found against the last status even across an outage.
+/// assert_eq!(observe(Ok(&old)), ["up", "motd", "version", "+alice", "-bob"]);
+/// ```
+#[derive(Debug, Default)]
+pub struct Watch {
+    /// Whether the server is up, or `None` before it is known.
+    up: Option<bool>,
+    /// The number of pings in a row that failed.
+    failures: usize,
+    /// The last status the server answered with.
+    last: Option<ServerStatus>,
+}
+
+impl Watch {
+    /// Account for the result of pinging the server, returning what changed.
+    pub fn observe(
+        &mut self,
+        result: Result<ServerStatus, Error>,
+        options: &MonitorOptions,
+    ) -> Vec<MonitorEventKind> {
+        let status = match result {
+            Ok(status) => status,
+            Err(error) => {
+                self.failures += 1;
+
+                if self.up != Some(false) && self.failures >= options.failures_before_down.max(1) {
+                    self.up = Some(false);
+                    return vec![MonitorEventKind::Down(error)];
+                }
+
+                return Vec::new();
+            }
+        };
+
+        self.failures = 0;
+
+        let mut events = match &self.last {
+            Some(last) => changes(last, &status),
+            None => Vec::new(),
+        };
+
+        if self.up != Some(true) {
+            self.up = Some(true);
+            events.insert(0, MonitorEventKind::Up(status.clone()));
+        }
+
+        self.last = Some(status);
+        events
+    }
+}
+
+/// The differences between two statuses of the same server.
+fn changes(old: &ServerStatus, new: &ServerStatus) -> Vec<MonitorEventKind> {
+    let mut events = Vec::new();
+
+    if old.motd != new.motd {
+        events.push(MonitorEventKind::MotdChanged {
+            old: old.motd.clone(),
+            new: new.motd.clone(),
+        });
+    }
+
+    if old.version_name != new.version_name || old.protocol != new.protocol {
+        events.push(MonitorEventKind::VersionChanged {
+            old_name: old.version_name.clone(),
+            new_name: new.version_name.clone(),
+            old_protocol: old.protocol,
+            new_protocol: new.protocol,
+        });
+    }
+
+    if old.icon != new.icon {
+        events.push(MonitorEventKind::FaviconChanged {
+            old: old.icon.clone(),
+            new: new.icon.clone(),
+        });
+    }
+
+    let old_sample = old.sample.as_deref().unwrap_or_default();
+    let new_sample = new.sample.as_deref().unwrap_or_default();
+
+    events.extend(
+        new_sample
+            .iter()
+            .filter(|player| !old_sample.contains(player))
+            .cloned()
+            .map(MonitorEventKind::PlayerAppeared),
+    );
+    events.extend(
+        old_sample
+            .iter()
+            .filter(|player| !new_sample.contains(player))
+            .cloned()
+            .map(MonitorEventKind::PlayerDisappeared),
+    );
+
+    events
+}
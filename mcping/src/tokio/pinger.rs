@@ -0,0 +1,158 @@
+//! A long-lived client for pinging many servers.
+
+use std::{future::Future, sync::Arc, time::Duration};
+use trust_dns_resolver::TokioAsyncResolver;
+
+use crate::{
+    tokio::{
+        auto,
+        connect::{self, Net},
+        retry::retry,
+    },
+    Attempt, AutoResponse, BedrockResponse, CacheKey, Error, JavaResponse, PingerOptions,
+    ResponseCache, Retried,
+};
+
+/// A client that keeps its DNS resolver, settings and cache between pings.
+///
+/// This is the async version of [`crate::Pinger`], and has to be created and
+/// used from within a tokio runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async {
+/// use mcping::{tokio::Pinger, PingerOptions};
+/// use std::time::Duration;
+///
+/// let pinger = Pinger::new(PingerOptions {
+///     timeout: Some(Duration::from_secs(5)),
+///     ..Default::default()
+/// })?;
+///
+/// let (latency, response) = pinger.ping_java("mc.hypixel.net").await?;
+/// let (latency, response) = pinger.ping_bedrock("play.nethergames.org").await?;
+/// # Ok::<(), mcping::Error>(())
+/// # };
+/// ```
+#[derive(Clone)]
+pub struct Pinger {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    options: PingerOptions,
+    resolver: TokioAsyncResolver,
+    cache: Option<ResponseCache>,
+}
+
+impl Pinger {
+    /// Create a pinger, setting up its DNS resolver.
+    pub fn new(options: PingerOptions) -> Result<Self, Error> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                resolver: connect::resolver()?,
+                cache: options.cache.map(ResponseCache::new),
+                options,
+            }),
+        })
+    }
+
+    /// The settings of the pinger.
+    pub fn options(&self) -> &PingerOptions {
+        &self.inner.options
+    }
+
+    /// Ping a Java server.
+    pub async fn ping_java(&self, address: &str) -> Result<(Duration, JavaResponse), Error> {
+        self.ping_java_retried(address)
+            .await
+            .map(|(latency, retried)| (latency, retried.response))
+    }
+
+    /// Ping a Java server, returning the outcome of every attempt too.
+    pub async fn ping_java_retried(
+        &self,
+        address: &str,
+    ) -> Result<(Duration, Retried<JavaResponse>), Error> {
+        let java = self.inner.options.java(address);
+        let key = java.cache_key()?;
+        let pinger = self.clone();
+
+        self.cached(key, async move {
+            let net = pinger.net();
+            retry(&pinger.inner.options.retry, || java.ping_with_async(&net)).await
+        })
+        .await
+    }
+
+    /// Ping a Bedrock server.
+    pub async fn ping_bedrock(&self, address: &str) -> Result<(Duration, BedrockResponse), Error> {
+        self.ping_bedrock_retried(address)
+            .await
+            .map(|(latency, retried)| (latency, retried.response))
+    }
+
+    /// Ping a Bedrock server, returning the outcome of every attempt too.
+    pub async fn ping_bedrock_retried(
+        &self,
+        address: &str,
+    ) -> Result<(Duration, Retried<BedrockResponse>), Error> {
+        let bedrock = self.inner.options.bedrock(address);
+        let key = bedrock.cache_key()?;
+        let pinger = self.clone();
+
+        self.cached(key, async move {
+            let net = pinger.net();
+            retry(&pinger.inner.options.retry, || {
+                bedrock.ping_with_async(&net)
+            })
+            .await
+        })
+        .await
+    }
+
+    /// Ping a server of either edition, like [`Auto`](crate::Auto) does.
+    pub async fn ping_any(&self, address: &str) -> Result<(Duration, AutoResponse), Error> {
+        let (java, java_address) = (self.clone(), address.to_string());
+        let (bedrock, bedrock_address) = (self.clone(), address.to_string());
+
+        auto::race(
+            async move { java.ping_java(&java_address).await },
+            async move { bedrock.ping_bedrock(&bedrock_address).await },
+            self.inner.options.grace_period,
+        )
+        .await
+    }
+
+    async fn cached<R, Fut>(&self, key: String, ping: Fut) -> Result<(Duration, Retried<R>), Error>
+    where
+        R: Clone + Send + Sync + 'static,
+        Fut: Future<Output = Result<((Duration, R), Vec<Attempt>), Error>> + Send + 'static,
+    {
+        let ((latency, response), attempts) = match &self.inner.cache {
+            Some(cache) => cache.get_or_ping_async(key, ping).await?,
+            None => ping.await?,
+        };
+
+        Ok((latency, Retried { response, attempts }))
+    }
+
+    fn net(&self) -> Net<'_> {
+        Net {
+            resolver: &self.inner.resolver,
+            bind_address: self.inner.options.bind_address,
+            proxy: self.inner.options.proxy.as_ref(),
+            address_policy: self.inner.options.address_policy.as_ref(),
+            rate_limiter: self.inner.options.rate_limiter.as_ref(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Pinger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pinger")
+            .field("options", &self.inner.options)
+            .finish()
+    }
+}
@@ -0,0 +1,13 @@
+//! Limiting how often the same server is pinged.
+
+use std::net::IpAddr;
+
+use crate::{Error, RateLimiter};
+
+impl RateLimiter {
+    /// Wait until a ping to `ip`, resolved from `host`, is allowed.
+    pub(crate) async fn wait_async(&self, host: &str, ip: IpAddr) -> Result<(), Error> {
+        tokio::time::sleep(self.reserve(host, ip)?).await;
+        Ok(())
+    }
+}
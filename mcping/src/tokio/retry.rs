@@ -0,0 +1,59 @@
+//! Retrying pings that fail with transient errors.
+
+use async_trait::async_trait;
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    retry::{deadline_passed, Attempts, Next},
+    tokio::AsyncPingable,
+    Attempt, Error, Retried, Retry, RetryPolicy,
+};
+
+#[async_trait]
+impl<P> AsyncPingable for Retry<P>
+where
+    P: AsyncPingable + Clone + Send + Sync,
+    P::Response: Send,
+{
+    type Response = Retried<P::Response>;
+
+    async fn ping(self) -> Result<(Duration, Self::Response), Error> {
+        let pingable = self.pingable;
+
+        retry(&self.policy, || pingable.clone().ping())
+            .await
+            .map(|((latency, response), attempts)| (latency, Retried { response, attempts }))
+    }
+}
+
+/// Call `ping` until it succeeds or the policy gives up.
+///
+/// See [`crate::retry::retry`].
+pub(crate) async fn retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    mut ping: F,
+) -> Result<(T, Vec<Attempt>), Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut attempts = Attempts::new();
+
+    loop {
+        let started = Instant::now();
+        let result = match attempts.remaining(policy) {
+            Some(remaining) => tokio::time::timeout(remaining, ping())
+                .await
+                .unwrap_or_else(|_| Err(deadline_passed())),
+            None => ping().await,
+        };
+
+        match attempts.record(policy, started, result) {
+            Next::Done(result) => return result,
+            Next::Wait(delay) => tokio::time::sleep(delay).await,
+        }
+    }
+}
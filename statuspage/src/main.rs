@@ -0,0 +1,192 @@
+use anyhow::Context as AnyhowContext;
+use mcping::{Auto, BatchOptions, Bedrock, History, Java, Record, Resolution, Retention, Target};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+mod page;
+
+use page::{Card, Page};
+
+/// How far back the sparklines go.
+const SPARKLINE_SPAN: u64 = 24 * 60 * 60;
+
+fn main() -> Result<(), anyhow::Error> {
+    let config_file = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "statuspage.toml".to_string());
+    let cfg = load_config(&config_file).with_context(|| "failed to load config")?;
+
+    let history = cfg
+        .history
+        .as_ref()
+        .map(|path| History::open(path, Retention::default()))
+        .transpose()
+        .with_context(|| "failed to open the history")?;
+
+    let timeout = Duration::from_secs(cfg.timeout);
+    let targets = cfg.servers.iter().map(|server| {
+        let server_address = server.address.clone();
+        match server.edition {
+            Edition::Java => Target::from(Java {
+                server_address,
+                timeout: Some(timeout),
+                ..Default::default()
+            }),
+            Edition::Bedrock => Target::from(Bedrock {
+                server_address,
+                timeout: Some(timeout),
+                ..Default::default()
+            }),
+            Edition::Auto => Target::from(Auto {
+                server_address,
+                timeout: Some(timeout),
+                ..Default::default()
+            }),
+        }
+    });
+
+    let mut results: Vec<_> = mcping::get_status_batch(targets, BatchOptions::default())
+        .map(|result| (result.index, result.result))
+        .collect();
+    results.sort_by_key(|(index, _)| *index);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let mut cards = Vec::with_capacity(results.len());
+    for (server, (_, result)) in cfg.servers.iter().zip(results) {
+        let key = server.key();
+
+        let (points, uptime) = match &history {
+            Some(history) => {
+                history
+                    .record(&Record::new(&key, result.as_ref()))
+                    .with_context(|| format!("failed to record the status of {}", key))?;
+
+                let from = now.saturating_sub(SPARKLINE_SPAN);
+                let points = history.range(&key, Resolution::FiveMinutes, from, now)?;
+                let uptime = history
+                    .summary(&key, Resolution::FiveMinutes, from, now)?
+                    .uptime();
+                (points, uptime)
+            }
+            None => (Vec::new(), None),
+        };
+
+        cards.push(Card {
+            name: server.name.as_deref().unwrap_or(&server.address),
+            address: &server.address,
+            status: result.map_err(|e| e.to_string()),
+            points,
+            uptime,
+        });
+    }
+
+    let html = Page {
+        title: &cfg.title,
+        refresh: cfg.refresh,
+        generated_at: now,
+        span: SPARKLINE_SPAN,
+        cards: &cards,
+    }
+    .render();
+
+    write_atomically(&cfg.output, &html)
+        .with_context(|| format!("failed to write '{}'", cfg.output.display()))
+}
+
+/// Configuration file with the servers to show and where to write the page.
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(default = "default_title")]
+    title: String,
+    /// The file the page is written to.
+    #[serde(default = "default_output")]
+    output: PathBuf,
+    /// The timeout of each ping, in seconds.
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+    /// How often browsers reload the page, in seconds.
+    refresh: Option<u64>,
+    /// The database the results are recorded in, for the sparklines.
+    history: Option<PathBuf>,
+    #[serde(default)]
+    servers: Vec<Server>,
+}
+
+fn default_title() -> String {
+    "Server status".to_string()
+}
+
+fn default_output() -> PathBuf {
+    "status/index.html".into()
+}
+
+fn default_timeout() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    /// The name shown on the card, the address if omitted.
+    name: Option<String>,
+    address: String,
+    #[serde(default)]
+    edition: Edition,
+}
+
+impl Server {
+    /// The key the results of the server are recorded under.
+    fn key(&self) -> String {
+        format!("{}/{}", self.edition.as_str(), self.address)
+    }
+}
+
+/// The edition a server is pinged as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Edition {
+    Java,
+    Bedrock,
+    #[default]
+    Auto,
+}
+
+impl Edition {
+    fn as_str(self) -> &'static str {
+        match self {
+            Edition::Java => "java",
+            Edition::Bedrock => "bedrock",
+            Edition::Auto => "auto",
+        }
+    }
+}
+
+/// Loads the config file at `path`.
+fn load_config(path: &str) -> Result<Config, anyhow::Error> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read file '{}'", path))?;
+    toml::from_str(&contents)
+        .with_context(|| format!("failed to parse TOML loaded from file '{}'", path))
+}
+
+/// Write `contents` to `path` through a temporary file, so that the page is
+/// never served half-written.
+fn write_atomically(path: &Path, contents: &str) -> Result<(), anyhow::Error> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
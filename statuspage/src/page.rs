@@ -0,0 +1,312 @@
+//! Rendering the status page.
+
+use mcping::{escape_html, format_utc, Chat, Point, ServerStatus};
+use std::fmt::Write;
+
+/// The size of a sparkline, in pixels.
+const SPARKLINE_WIDTH: f64 = 240.0;
+const SPARKLINE_HEIGHT: f64 = 40.0;
+
+/// The whole status page.
+pub struct Page<'a> {
+    pub title: &'a str,
+    /// How often browsers reload the page, in seconds.
+    pub refresh: Option<u64>,
+    /// When the page was generated, in seconds since the Unix epoch.
+    pub generated_at: u64,
+    /// How far back the sparklines go, in seconds.
+    pub span: u64,
+    pub cards: &'a [Card<'a>],
+}
+
+/// A server shown on the page.
+pub struct Card<'a> {
+    pub name: &'a str,
+    pub address: &'a str,
+    /// The status of the server, or why it couldn't be pinged.
+    pub status: Result<ServerStatus, String>,
+    /// The recorded history of the server, oldest first.
+    pub points: Vec<Point>,
+    /// The fraction of recorded pings that succeeded.
+    pub uptime: Option<f64>,
+}
+
+const STYLE: &str = "\
+body{margin:0;padding:2em;background:#1e1f22;color:#dbdee1;font-family:system-ui,sans-serif}\
+h1{margin:0 0 .2em}\
+.updated{color:#949ba4;margin:0 0 2em}\
+.cards{display:grid;grid-template-columns:repeat(auto-fill,minmax(26em,1fr));gap:1em}\
+.card{background:#2b2d31;border-radius:8px;padding:1em;display:grid;grid-template-columns:64px 1fr;gap:0 1em}\
+.icon{width:64px;height:64px;image-rendering:pixelated;border-radius:4px;background:#404249}\
+.name{font-weight:bold;font-size:1.1em}\
+.address{color:#949ba4;font-size:.9em}\
+.dot{display:inline-block;width:.7em;height:.7em;border-radius:50%;margin-right:.4em}\
+.up{background:#23a55a}.down{background:#f23f43}\
+.motd{grid-column:1/3;margin:.8em 0;padding:.5em;background:#111214;border-radius:4px;\
+font-family:monospace;white-space:pre-wrap;color:#aaa}\
+.stats{grid-column:1/3;display:flex;flex-wrap:wrap;gap:.3em 1.5em;font-size:.9em}\
+.stats b{color:#949ba4;font-weight:normal}\
+.error{grid-column:1/3;color:#f23f43;font-size:.9em;margin-top:.8em}\
+.history{grid-column:1/3;margin-top:.8em;font-size:.8em;color:#949ba4}\
+.history svg{display:block;margin-top:.3em}";
+
+impl Page<'_> {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "<!DOCTYPE html>");
+        let _ = writeln!(out, "<html lang=\"en\">");
+        let _ = writeln!(out, "<head>");
+        let _ = writeln!(out, "<meta charset=\"utf-8\">");
+        let _ = writeln!(
+            out,
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+        );
+        if let Some(refresh) = self.refresh {
+            let _ = writeln!(out, "<meta http-equiv=\"refresh\" content=\"{}\">", refresh);
+        }
+        // The title element can't hold markup, so only the text is kept.
+        let title = Chat::from_legacy(self.title).plain_text();
+        let _ = writeln!(out, "<title>{}</title>", html(&title));
+        let _ = writeln!(out, "<style>{}</style>", STYLE);
+        let _ = writeln!(out, "</head>");
+        let _ = writeln!(out, "<body>");
+        let _ = writeln!(out, "<h1>{}</h1>", html(self.title));
+
+        let online = self.cards.iter().filter(|card| card.status.is_ok()).count();
+        let _ = writeln!(
+            out,
+            "<p class=\"updated\">{} of {} servers online &middot; updated {}</p>",
+            online,
+            self.cards.len(),
+            format_utc(self.generated_at)
+        );
+
+        let _ = writeln!(out, "<div class=\"cards\">");
+        for card in self.cards {
+            self.render_card(&mut out, card);
+        }
+        let _ = writeln!(out, "</div>");
+        let _ = writeln!(out, "</body>");
+        let _ = writeln!(out, "</html>");
+
+        out
+    }
+
+    fn render_card(&self, out: &mut String, card: &Card<'_>) {
+        let _ = writeln!(out, "<section class=\"card\">");
+
+        let icon = match &card.status {
+            Ok(status) => status.decode_icon(),
+            Err(_) => Ok(None),
+        };
+        match &icon {
+            Ok(Some(icon)) => {
+                let _ = writeln!(
+                    out,
+                    "<img class=\"icon\" src=\"{}\" alt=\"\">",
+                    icon.to_data_uri()
+                );
+            }
+            _ => {
+                let _ = writeln!(out, "<div class=\"icon\"></div>");
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "<div><div class=\"name\"><span class=\"dot {}\"></span>{}</div>\
+             <div class=\"address\">{}</div></div>",
+            if card.status.is_ok() { "up" } else { "down" },
+            html(card.name),
+            escape_html(card.address)
+        );
+
+        match &card.status {
+            Ok(status) => {
+                let _ = writeln!(out, "<div class=\"motd\">{}</div>", status.motd.to_html());
+
+                let players = match (status.players_online, status.players_max) {
+                    (Some(online), Some(max)) => format!("{} / {}", online, max),
+                    (Some(online), None) => online.to_string(),
+                    _ => "?".to_string(),
+                };
+                let _ = writeln!(
+                    out,
+                    "<div class=\"stats\"><span><b>Players</b> {}</span>\
+                     <span><b>Version</b> {}</span>\
+                     <span><b>Latency</b> {:.0} ms</span>\
+                     <span><b>Edition</b> {}</span></div>",
+                    players,
+                    Chat::from_legacy(&status.version_name).to_html(),
+                    status.latency.as_secs_f64() * 1000.0,
+                    status.edition
+                );
+
+                if let Err(error) = &icon {
+                    let _ = writeln!(
+                        out,
+                        "<div class=\"error\">Invalid icon: {}</div>",
+                        escape_html(&error.to_string())
+                    );
+                }
+            }
+            Err(error) => {
+                let _ = writeln!(
+                    out,
+                    "<div class=\"error\">Offline: {}</div>",
+                    escape_html(error)
+                );
+            }
+        }
+
+        if !card.points.is_empty() {
+            let _ = write!(
+                out,
+                "<div class=\"history\">Players, last {}",
+                format_span(self.span)
+            );
+            if let Some(uptime) = card.uptime {
+                let _ = write!(out, " &middot; {:.2}% uptime", uptime * 100.0);
+            }
+            let _ = writeln!(
+                out,
+                "{}</div>",
+                sparkline(&card.points, self.generated_at, self.span)
+            );
+        }
+
+        let _ = writeln!(out, "</section>");
+    }
+}
+
+/// An SVG line of the most players online over time, with the times every
+/// ping failed marked in red.
+fn sparkline(points: &[Point], now: u64, span: u64) -> String {
+    let from = now.saturating_sub(span);
+    let x = |timestamp: u64| {
+        (timestamp.saturating_sub(from)) as f64 / span.max(1) as f64 * SPARKLINE_WIDTH
+    };
+
+    let peak = points
+        .iter()
+        .filter_map(|point| point.players_online_max)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let y =
+        |players: i64| SPARKLINE_HEIGHT - 2.0 - players as f64 / peak * (SPARKLINE_HEIGHT - 6.0);
+
+    let mut svg = format!(
+        "<svg width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = SPARKLINE_WIDTH,
+        h = SPARKLINE_HEIGHT
+    );
+
+    let line: Vec<_> = points
+        .iter()
+        .filter_map(|point| {
+            let players = point.players_online_max?;
+            Some(format!("{:.1},{:.1}", x(point.timestamp), y(players)))
+        })
+        .collect();
+    if !line.is_empty() {
+        let _ = write!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"#5865f2\" stroke-width=\"1.5\" points=\"{}\"/>",
+            line.join(" ")
+        );
+    }
+
+    for point in points.iter().filter(|point| point.up == 0) {
+        let _ = write!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{}\" width=\"2\" height=\"4\" fill=\"#f23f43\"/>",
+            x(point.timestamp).min(SPARKLINE_WIDTH - 2.0),
+            SPARKLINE_HEIGHT - 4.0
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// `text` escaped for the page, with any formatting codes in it applied.
+fn html(text: &str) -> String {
+    Chat::from_legacy(text).to_html()
+}
+
+/// Describe a span of seconds in the largest unit it is a whole number of,
+/// such as `24 hours` or `7 days`.
+fn format_span(span: u64) -> String {
+    let (count, unit) = match span {
+        span if span >= 2 * 86_400 && span % 86_400 == 0 => (span / 86_400, "day"),
+        span if span >= 3600 && span % 3600 == 0 => (span / 3600, "hour"),
+        span if span >= 60 && span % 60 == 0 => (span / 60, "minute"),
+        span => (span, "second"),
+    };
+
+    if count == 1 {
+        unit.to_string()
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64, up: bool) -> Point {
+        Point {
+            timestamp,
+            samples: 1,
+            up: up as u64,
+            latency_mean: None,
+            latency_min: None,
+            latency_max: None,
+            players_online_mean: None,
+            players_online_max: Some(3),
+            players_max: None,
+            version: None,
+            motd_hash: None,
+        }
+    }
+
+    #[test]
+    fn render_escapes_text() {
+        let cards = [Card {
+            name: "§cRed <b>",
+            address: "mc.example.com §c<i>",
+            status: Err("refused & §creset".to_string()),
+            points: vec![point(1_699_990_000, true), point(1_699_995_000, false)],
+            uptime: Some(0.5),
+        }];
+        let html = Page {
+            title: "My <servers>",
+            refresh: None,
+            generated_at: 1_700_000_000,
+            span: 7 * 86_400,
+            cards: &cards,
+        }
+        .render();
+
+        assert!(html.contains("<title>My &lt;servers&gt;</title>"));
+        assert!(html.contains("Red &lt;b&gt;"));
+        assert!(!html.contains("<b>") && !html.contains("<i>"));
+        // Addresses and errors are shown as they are, codes included.
+        assert!(html.contains("<div class=\"address\">mc.example.com §c&lt;i&gt;</div>"));
+        assert!(html.contains("Offline: refused &amp; §creset"));
+        assert!(html.contains("Players, last 7 days &middot; 50.00% uptime"));
+        assert!(html.contains("updated 2023-11-14 22:13 UTC"));
+    }
+
+    #[test]
+    fn format_span_uses_the_largest_whole_unit() {
+        assert_eq!(format_span(86_400), "24 hours");
+        assert_eq!(format_span(7 * 86_400), "7 days");
+        assert_eq!(format_span(3600), "hour");
+        assert_eq!(format_span(90 * 60), "90 minutes");
+        assert_eq!(format_span(45), "45 seconds");
+    }
+}